@@ -0,0 +1,31 @@
+// A summary of everything wrong with a file, gathered in one pass without
+// giving up at the first problem.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct AuditReport {
+    pub signature_ok: bool,
+    pub chunk_count: usize,
+    // Indices of chunks whose stored CRC doesn't match their contents.
+    pub bad_crc_chunks: Vec<usize>,
+    pub ordering_issues: Vec<String>,
+    // Distinct chunk types not defined by the PNG spec, in sorted order.
+    pub unknown_types: Vec<String>,
+    pub trailing_bytes_len: usize,
+}
+
+impl AuditReport {
+    // Agrees with `Png::validate_bytes`: data after IEND is only reported, as
+    // it's kept as trailing data, while a file cut short is caught by its last
+    // chunk not being IEND.
+    pub fn is_clean(&self) -> bool {
+        self.signature_ok && self.bad_crc_chunks.is_empty() && self.ordering_issues.is_empty()
+    }
+}
+
+// A chunk type appearing more than once in a file.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DuplicateType {
+    pub chunk_type: String,
+    pub count: usize,
+    // Whether the spec allows at most one chunk of this type.
+    pub violation: bool,
+}
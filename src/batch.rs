@@ -0,0 +1,408 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum GlobExpandError {
+    #[error("Invalid glob pattern {pattern:?}: {source}")]
+    Pattern {
+        pattern: String,
+        source: glob::PatternError,
+    },
+    #[error(transparent)]
+    Glob(#[from] glob::GlobError),
+    #[error("No files matched {0:?}.")]
+    NoMatches(String),
+}
+
+#[derive(Error, Debug)]
+#[error("{failed} of {total} files failed.")]
+pub struct BatchError {
+    failed: usize,
+    total: usize,
+}
+
+pub struct BatchReport {
+    total: usize,
+    failures: Vec<(PathBuf, String)>,
+}
+
+impl BatchReport {
+    #[allow(dead_code)]
+    pub fn failures(&self) -> &[(PathBuf, String)] {
+        &self.failures
+    }
+
+    pub fn succeeded(&self) -> usize {
+        self.total - self.failures.len()
+    }
+
+    pub fn summary(&self) -> Vec<String> {
+        self.failures
+            .iter()
+            .map(|(path, err)| format!("{}: {}", path.display(), err))
+            .collect()
+    }
+
+    pub fn into_result(self) -> Result<(), BatchError> {
+        if self.failures.is_empty() {
+            Ok(())
+        } else {
+            Err(BatchError {
+                failed: self.failures.len(),
+                total: self.total,
+            })
+        }
+    }
+}
+
+// Carries on past any file which fails, so one bad file doesn't hide problems
+// with the rest.
+pub fn run_batch<F>(paths: &[PathBuf], mut f: F) -> BatchReport
+where
+    F: FnMut(&Path) -> Result<(), String>,
+{
+    let failures = paths
+        .iter()
+        .filter_map(|path| f(path).err().map(|err| (path.clone(), err)))
+        .collect();
+
+    BatchReport {
+        total: paths.len(),
+        failures,
+    }
+}
+
+// Adds one file's chunk counts into the running totals for a whole batch.
+pub fn merge_counts(totals: &mut BTreeMap<String, usize>, counts: BTreeMap<String, usize>) {
+    for (chunk_type, count) in counts {
+        *totals.entry(chunk_type).or_insert(0) += count;
+    }
+}
+
+// Expands any glob patterns among `paths`, for shells which don't do so
+// themselves. A pattern matching nothing is kept as it is, so that it gets
+// reported as missing rather than silently skipped.
+pub fn expand_globs(paths: &[PathBuf]) -> Result<Vec<PathBuf>, GlobExpandError> {
+    let mut expanded = Vec::new();
+
+    for path in paths {
+        let Some(pattern) = path
+            .to_str()
+            .filter(|pattern| pattern.contains(['*', '?', '[']))
+        else {
+            expanded.push(path.clone());
+            continue;
+        };
+
+        let matches = glob_matches(pattern)?;
+        if matches.is_empty() {
+            expanded.push(path.clone());
+        } else {
+            expanded.extend(matches);
+        }
+    }
+
+    Ok(expanded)
+}
+
+// As `expand_globs` for a single path, except that a pattern matching nothing
+// is an error, as the pattern itself almost certainly isn't a file. A file
+// whose name just happens to contain wildcards is left alone.
+pub fn expand_path(path: &Path) -> Result<Vec<PathBuf>, GlobExpandError> {
+    let pattern = match path.to_str() {
+        Some(pattern) if pattern.contains(['*', '?', '[']) && !path.exists() => pattern,
+        _ => return Ok(vec![path.to_path_buf()]),
+    };
+
+    let matches = glob_matches(pattern)?;
+    if matches.is_empty() {
+        return Err(GlobExpandError::NoMatches(pattern.to_string()));
+    }
+    Ok(matches)
+}
+
+fn glob_matches(pattern: &str) -> Result<Vec<PathBuf>, GlobExpandError> {
+    let matches = glob::glob(pattern)
+        .map_err(|source| GlobExpandError::Pattern {
+            pattern: pattern.to_string(),
+            source,
+        })?
+        .collect::<Result<Vec<PathBuf>, glob::GlobError>>()?;
+    Ok(matches)
+}
+
+fn has_png_extension(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("png"))
+}
+
+// Options controlling how directories are searched for PNG files.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WalkOptions {
+    pub follow_symlinks: bool,
+    // How many directories deep to look, where files directly inside a
+    // directory given are at depth 1. `None` means no limit.
+    pub max_depth: Option<usize>,
+}
+
+fn walk_dir(
+    dir: &Path,
+    depth: usize,
+    options: WalkOptions,
+    visited: &mut HashSet<PathBuf>,
+    found: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    if options.max_depth.is_some_and(|max_depth| depth > max_depth) {
+        return Ok(());
+    }
+
+    // Following symlinks could lead back to a directory already walked.
+    if !visited.insert(fs::canonicalize(dir)?) {
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<PathBuf>>>()?;
+    entries.sort();
+
+    for path in entries {
+        if !options.follow_symlinks && fs::symlink_metadata(&path)?.file_type().is_symlink() {
+            continue;
+        }
+
+        if path.is_dir() {
+            walk_dir(&path, depth + 1, options, visited, found)?;
+        } else if has_png_extension(&path) {
+            found.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+// Replaces any directories among `paths` with the .png files found beneath
+// them. Symlinks inside directories are skipped unless `follow_symlinks` is
+// set, while paths given explicitly are always kept.
+pub fn collect_png_paths(paths: &[PathBuf], options: WalkOptions) -> io::Result<Vec<PathBuf>> {
+    let mut visited = HashSet::new();
+    let mut found = Vec::new();
+
+    for path in paths {
+        if path.is_dir() {
+            walk_dir(path, 1, options, &mut visited, &mut found)?;
+        } else {
+            found.push(path.clone());
+        }
+    }
+
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::Png;
+    use std::fs;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pngme_{}_{}", std::process::id(), name))
+    }
+
+    fn parse_file(path: &Path) -> Result<(), String> {
+        let data = fs::read(path).map_err(|err| err.to_string())?;
+        Png::try_from(data.as_ref())
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn test_run_batch_reports_every_failure() {
+        let valid = temp_path("batch_valid.png");
+        let invalid = temp_path("batch_invalid.png");
+        let missing = temp_path("batch_missing.png");
+        fs::write(&valid, Png::from_chunks(Vec::new()).as_bytes()).unwrap();
+        fs::write(&invalid, b"not a png").unwrap();
+
+        let paths = vec![invalid.clone(), valid.clone(), missing.clone()];
+        let mut visited = 0;
+        let report = run_batch(&paths, |path| {
+            visited += 1;
+            parse_file(path)
+        });
+
+        assert_eq!(visited, 3);
+        let failed: Vec<&PathBuf> = report.failures().iter().map(|(path, _)| path).collect();
+        assert_eq!(failed, vec![&invalid, &missing]);
+        assert_eq!(report.summary().len(), 2);
+
+        let err = report.into_result().unwrap_err();
+        assert_eq!(err.to_string(), "2 of 3 files failed.");
+
+        fs::remove_file(&valid).unwrap();
+        fs::remove_file(&invalid).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_png_paths_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let root = temp_path("batch_walk");
+        let elsewhere = temp_path("batch_walk_elsewhere");
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::create_dir_all(&elsewhere).unwrap();
+        fs::write(root.join("nested").join("real.png"), b"").unwrap();
+        fs::write(root.join("notes.txt"), b"").unwrap();
+        fs::write(elsewhere.join("other.png"), b"").unwrap();
+        symlink(root.join("nested").join("real.png"), root.join("link.png")).unwrap();
+        symlink(&elsewhere, root.join("linked_dir")).unwrap();
+        // A cycle back to the top, which must not be walked forever.
+        symlink(&root, root.join("nested").join("loop")).unwrap();
+
+        let options = WalkOptions::default();
+        let found = collect_png_paths(std::slice::from_ref(&root), options).unwrap();
+        assert_eq!(found, vec![root.join("nested").join("real.png")]);
+
+        let options = WalkOptions {
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        let found = collect_png_paths(std::slice::from_ref(&root), options).unwrap();
+        assert_eq!(
+            found,
+            vec![
+                root.join("link.png"),
+                root.join("linked_dir").join("other.png"),
+                root.join("nested").join("real.png"),
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(&elsewhere).unwrap();
+    }
+
+    #[test]
+    fn test_collect_png_paths_max_depth() {
+        let root = temp_path("batch_depth");
+        let deep = root.join("one").join("two");
+        fs::create_dir_all(&deep).unwrap();
+        fs::write(root.join("top.png"), b"").unwrap();
+        fs::write(root.join("one").join("middle.png"), b"").unwrap();
+        fs::write(deep.join("bottom.png"), b"").unwrap();
+
+        let found = |max_depth| {
+            let options = WalkOptions {
+                max_depth,
+                ..Default::default()
+            };
+            collect_png_paths(std::slice::from_ref(&root), options).unwrap()
+        };
+
+        assert_eq!(found(Some(1)), vec![root.join("top.png")]);
+        assert_eq!(
+            found(Some(2)),
+            vec![root.join("one").join("middle.png"), root.join("top.png")]
+        );
+        assert_eq!(found(None).len(), 3);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_expand_globs() {
+        let dir = temp_path("batch_glob");
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["a.png", "b.png", "notes.txt"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let pattern = dir.join("*.png");
+        let missing = dir.join("missing.png");
+        let expanded = expand_globs(&[pattern, missing.clone()]).unwrap();
+
+        assert_eq!(
+            expanded,
+            vec![dir.join("a.png"), dir.join("b.png"), missing]
+        );
+
+        let unmatched = dir.join("*.jpg");
+        let expanded = expand_globs(std::slice::from_ref(&unmatched)).unwrap();
+        assert_eq!(expanded, vec![unmatched]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_expand_path() {
+        let dir = temp_path("batch_expand_path");
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["a.png", "b.png", "notes.txt"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let expanded = expand_path(&dir.join("*.png")).unwrap();
+        assert_eq!(expanded, vec![dir.join("a.png"), dir.join("b.png")]);
+
+        let plain = dir.join("notes.txt");
+        assert_eq!(expand_path(&plain).unwrap(), vec![plain]);
+
+        let result = expand_path(&dir.join("*.jpg"));
+        assert!(matches!(result, Err(GlobExpandError::NoMatches(_))));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("No files matched"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_merge_counts_across_files() {
+        use crate::{chunk::Chunk, chunk_type::ChunkType, format::format_frequency_table};
+        use std::str::FromStr;
+
+        let png_of = |types: &[&str]| {
+            let chunks = types
+                .iter()
+                .map(|chunk_type| Chunk::new(ChunkType::from_str(chunk_type).unwrap(), Vec::new()))
+                .collect();
+            Png::from_chunks(chunks).as_bytes()
+        };
+        let first = temp_path("stats_first.png");
+        let second = temp_path("stats_second.png");
+        fs::write(&first, png_of(&["IHDR", "IDAT", "IDAT", "IEND"])).unwrap();
+        fs::write(&second, png_of(&["IHDR", "ruSt", "IDAT", "IEND"])).unwrap();
+
+        let mut totals = BTreeMap::new();
+        let report = run_batch(&[first.clone(), second.clone()], |path| {
+            let data = fs::read(path).map_err(|err| err.to_string())?;
+            let png = Png::try_from(data.as_ref()).map_err(|err| err.to_string())?;
+            merge_counts(&mut totals, png.chunk_counts());
+            Ok(())
+        });
+        assert!(report.into_result().is_ok());
+
+        assert_eq!(
+            format_frequency_table(&totals),
+            vec!["IDAT 3", "IEND 2", "IHDR 2", "ruSt 1"]
+        );
+
+        fs::remove_file(&first).unwrap();
+        fs::remove_file(&second).unwrap();
+    }
+
+    #[test]
+    fn test_run_batch_all_succeed() {
+        let paths = vec![PathBuf::from("a.png"), PathBuf::from("b.png")];
+        let report = run_batch(&paths, |_| Ok(()));
+
+        assert!(report.summary().is_empty());
+        assert_eq!(report.succeeded(), 2);
+        assert!(report.into_result().is_ok());
+    }
+}
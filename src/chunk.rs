@@ -4,23 +4,40 @@ use thiserror::Error;
 
 use crc::Crc;
 
-use crate::chunk_type::{ChunkType, ChunkTypeError, CHUNK_TYPE_NUM_BYTES};
+use crate::{
+    chunk_type::{ChunkType, ChunkTypeError, CHUNK_TYPE_NUM_BYTES},
+    format::to_hex,
+    text::latin1_to_string,
+};
 
 pub const CHUNK_LENGTH_NUM_BYTES: usize = 4;
 pub const CHUNK_CHECK_NUM_BYTES: usize = 4;
 pub const CHUNK_METADATA_NUM_BYTES: usize =
     CHUNK_LENGTH_NUM_BYTES + CHUNK_CHECK_NUM_BYTES + CHUNK_TYPE_NUM_BYTES;
 
+// How many bytes of data Debug output shows, so huge chunks stay readable.
+const DEBUG_PREVIEW_NUM_BYTES: usize = 16;
+
 #[derive(Error, Debug)]
 pub enum ChunkError {
     #[error(transparent)]
     BadType(#[from] ChunkTypeError),
-    #[error("Given {0} bytes are insufficient to form chunk.")]
-    InsufficientBytes(usize),
+    #[error("Given {available} bytes are insufficient to form chunk, which needs {needed}.")]
+    InsufficientBytes { needed: usize, available: usize },
     #[error("Chunk failed checksum, expected {expected} but was given {actual}.")]
     BadChecksum { expected: u32, actual: u32 },
+    #[error("Chunk type {0} is invalid, as its third letter must be uppercase.")]
+    InvalidChunkType(ChunkType),
+    #[error("Found {0} bytes after the end of the chunk.")]
+    TrailingData(usize),
+    #[error("Malformed chunk at offset {offset}: {source}")]
+    AtOffset {
+        offset: usize,
+        source: Box<ChunkError>,
+    },
 }
 
+#[derive(Clone, PartialEq, Eq)]
 pub struct Chunk {
     length: u32,
     chunk_type: ChunkType,
@@ -42,6 +59,20 @@ impl Chunk {
         }
     }
 
+    // As `new`, but refuses chunk types which break the spec by setting the
+    // reserved bit.
+    pub fn new_checked(chunk_type: ChunkType, data: Vec<u8>) -> Result<Chunk, ChunkError> {
+        if !chunk_type.is_valid() {
+            return Err(ChunkError::InvalidChunkType(chunk_type));
+        }
+        Ok(Self::new(chunk_type, data))
+    }
+
+    #[allow(dead_code)]
+    pub fn new_from_str(chunk_type: ChunkType, message: &str) -> Chunk {
+        Self::new(chunk_type, message.as_bytes().to_vec())
+    }
+
     pub fn length(&self) -> u32 {
         self.length
     }
@@ -50,18 +81,114 @@ impl Chunk {
         &self.chunk_type
     }
 
+    // Strings which aren't exactly four bytes long never match.
+    pub fn type_eq_str(&self, chunk_type: &str) -> bool {
+        self.chunk_type.bytes() == chunk_type.as_bytes()
+    }
+
     pub fn data(&self) -> &[u8] {
         self.data.as_slice()
     }
 
+    // The size of the whole chunk once serialized, not just its data.
+    pub fn total_bytes(&self) -> usize {
+        self.length() as usize + CHUNK_METADATA_NUM_BYTES
+    }
+
+    // Replaces the data, keeping the length and CRC in step with it.
+    #[allow(dead_code)]
+    pub fn set_data(&mut self, data: Vec<u8>) {
+        *self = Chunk::new(self.chunk_type, data);
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
     pub fn crc(&self) -> u32 {
         self.checksum
     }
 
+    pub fn crc_matches(&self, expected: u32) -> bool {
+        self.checksum == expected
+    }
+
     pub fn data_as_string(&self) -> Result<String, FromUtf8Error> {
         String::from_utf8(self.data.clone())
     }
 
+    pub fn data_as_lossy_string(&self) -> String {
+        String::from_utf8_lossy(&self.data).into_owned()
+    }
+
+    // Standard text chunks are Latin-1 rather than UTF-8, and every byte is a
+    // valid Latin-1 character, so this can't fail.
+    pub fn data_as_latin1(&self) -> String {
+        latin1_to_string(&self.data)
+    }
+
+    // Unlike `try_from`, which ignores anything after the chunk, requires the
+    // bytes to hold exactly one chunk.
+    #[allow(dead_code)]
+    pub fn try_from_exact(bytes: &[u8]) -> Result<Chunk, ChunkError> {
+        let chunk = Chunk::try_from(bytes)?;
+        let chunk_len = chunk.total_bytes();
+
+        if bytes.len() > chunk_len {
+            return Err(ChunkError::TrailingData(bytes.len() - chunk_len));
+        }
+        Ok(chunk)
+    }
+
+    // Parses back-to-back chunks until the bytes run out, such as a chunk
+    // stream with no PNG signature.
+    #[allow(dead_code)]
+    pub fn parse_many(bytes: &[u8]) -> Result<Vec<Chunk>, ChunkError> {
+        let mut chunks = Vec::new();
+        let mut offset = 0;
+
+        while offset < bytes.len() {
+            let chunk = Chunk::try_from(&bytes[offset..]).map_err(|err| ChunkError::AtOffset {
+                offset,
+                source: Box::new(err),
+            })?;
+            offset += chunk.total_bytes();
+            chunks.push(chunk);
+        }
+
+        Ok(chunks)
+    }
+
+    // Up to `max` bytes of the data as hex, with an ellipsis if any were left
+    // off.
+    pub fn data_preview(&self, max: usize) -> String {
+        if self.data.len() > max {
+            format!("{}…", to_hex(&self.data[..max]))
+        } else {
+            to_hex(&self.data)
+        }
+    }
+
+    // Shannon entropy of the data in bits per byte, from 0 for a single
+    // repeated byte up to 8 for uniformly random data.
+    pub fn entropy(&self) -> f64 {
+        let mut counts = [0usize; 256];
+        for &byte in &self.data {
+            counts[byte as usize] += 1;
+        }
+
+        let len = self.data.len() as f64;
+        counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / len;
+                -p * p.log2()
+            })
+            .sum()
+    }
+
     pub fn as_bytes(&self) -> Vec<u8> {
         u32::to_be_bytes(self.length)
             .iter()
@@ -78,8 +205,12 @@ impl TryFrom<&[u8]> for Chunk {
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
         let value_len = value.len();
+        let insufficient = |needed| ChunkError::InsufficientBytes {
+            needed,
+            available: value_len,
+        };
         if value_len < CHUNK_METADATA_NUM_BYTES {
-            return Err(ChunkError::InsufficientBytes(value_len));
+            return Err(insufficient(CHUNK_METADATA_NUM_BYTES));
         }
 
         let (length_slice, remaining_bytes) = value.split_at(CHUNK_LENGTH_NUM_BYTES);
@@ -88,30 +219,34 @@ impl TryFrom<&[u8]> for Chunk {
         let length = u32::from_be_bytes(
             length_slice
                 .try_into()
-                .map_err(|_| ChunkError::InsufficientBytes(value_len))?,
+                .map_err(|_| insufficient(CHUNK_METADATA_NUM_BYTES))?,
         );
 
         let chunk_type = ChunkType::try_from(
             TryInto::<[u8; 4]>::try_into(type_slice)
-                .map_err(|_| ChunkError::InsufficientBytes(value_len))?,
+                .map_err(|_| insufficient(CHUNK_METADATA_NUM_BYTES))?,
         )?;
 
-        if remaining_bytes.len() < length as usize {
-            return Err(ChunkError::InsufficientBytes(value_len));
+        // The declared length is checked against what's actually there before
+        // anything is sliced or allocated, as a corrupt file can claim up to
+        // 4GiB. Should the total not fit in a usize, it can't be present either.
+        let needed = usize::try_from(length)
+            .ok()
+            .and_then(|length| length.checked_add(CHUNK_METADATA_NUM_BYTES))
+            .unwrap_or(usize::MAX);
+        if value_len < needed {
+            return Err(insufficient(needed));
         }
 
-        let (data, remaining_bytes) = remaining_bytes.split_at(length as usize);
-        let (crc_slice, _) = remaining_bytes.split_at(CHUNK_CHECK_NUM_BYTES);
+        let data_end = needed - CHUNK_METADATA_NUM_BYTES;
+        let (data, remaining_bytes) = remaining_bytes.split_at(data_end);
+        let crc_slice = &remaining_bytes[..CHUNK_CHECK_NUM_BYTES];
 
         let chunk = Chunk::new(chunk_type, Vec::from(data));
 
-        let crc = u32::from_be_bytes(
-            crc_slice
-                .try_into()
-                .map_err(|_| ChunkError::InsufficientBytes(value_len))?,
-        );
+        let crc = u32::from_be_bytes(crc_slice.try_into().map_err(|_| insufficient(needed))?);
 
-        if chunk.crc() == crc {
+        if chunk.crc_matches(crc) {
             Ok(chunk)
         } else {
             Err(ChunkError::BadChecksum {
@@ -122,23 +257,60 @@ impl TryFrom<&[u8]> for Chunk {
     }
 }
 
-impl fmt::Display for Chunk {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let data_repr = match self.data_as_string() {
-            Ok(data_str) => data_str,
-            Err(_) => "Not String Representable".to_string(),
+impl Chunk {
+    // As the `Display` output, but showing data that isn't UTF-8 lossily
+    // rather than omitting it.
+    pub fn display_lossy(&self) -> LossyChunk<'_> {
+        LossyChunk(self)
+    }
+
+    fn write_display(&self, f: &mut fmt::Formatter<'_>, lossy: bool) -> fmt::Result {
+        let data_repr = if self.chunk_type == ChunkType::TEXT {
+            self.data_as_latin1()
+        } else {
+            match self.data_as_string() {
+                Ok(data_str) => data_str,
+                Err(_) if lossy => self.data_as_lossy_string(),
+                Err(_) => "Not String Representable".to_string(),
+            }
         };
         write!(
             f,
-            "Length: {}\nType: {}\nData: {}\nCRC: {}",
+            "Length: {}\nType: {} {}\nData: {}\nCRC: {}",
             self.length(),
             self.chunk_type(),
+            self.chunk_type().flags(),
             data_repr,
             self.crc()
         )
     }
 }
 
+impl fmt::Display for Chunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_display(f, false)
+    }
+}
+
+pub struct LossyChunk<'a>(&'a Chunk);
+
+impl fmt::Display for LossyChunk<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.write_display(f, true)
+    }
+}
+
+impl fmt::Debug for Chunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Chunk")
+            .field("length", &self.length)
+            .field("chunk_type", &self.chunk_type.to_string())
+            .field("data", &self.data_preview(DEBUG_PREVIEW_NUM_BYTES))
+            .field("checksum", &self.checksum)
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -174,6 +346,27 @@ mod tests {
         assert_eq!(chunk.crc(), 2882656334);
     }
 
+    #[test]
+    fn test_new_chunk_checked() {
+        let chunk = Chunk::new_checked(ChunkType::from_str("RuSt").unwrap(), vec![1, 2]);
+        assert!(chunk.is_ok());
+
+        let chunk = Chunk::new_checked(ChunkType::from_str("Rust").unwrap(), vec![1, 2]);
+        assert!(matches!(chunk, Err(ChunkError::InvalidChunkType(_))));
+    }
+
+    #[test]
+    fn test_new_chunk_from_str() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let chunk = Chunk::new_from_str(chunk_type, "This is where your secret message will be!");
+        assert_eq!(chunk.length(), 42);
+        assert_eq!(chunk.crc(), 2882656334);
+        assert_eq!(
+            chunk.data_as_string().unwrap(),
+            "This is where your secret message will be!"
+        );
+    }
+
     #[test]
     fn test_chunk_length() {
         let chunk = testing_chunk();
@@ -186,6 +379,25 @@ mod tests {
         assert_eq!(chunk.chunk_type().to_string(), String::from("RuSt"));
     }
 
+    #[test]
+    fn test_chunk_type_eq_str() {
+        let chunk = testing_chunk();
+        assert!(chunk.type_eq_str("RuSt"));
+        assert!(!chunk.type_eq_str("RUST"));
+        assert!(!chunk.type_eq_str("RuS"));
+        assert!(!chunk.type_eq_str("RuStRuSt"));
+        assert!(!chunk.type_eq_str(""));
+    }
+
+    #[test]
+    fn test_chunk_is_empty() {
+        let chunk = Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new());
+        assert!(chunk.is_empty());
+
+        let chunk = testing_chunk();
+        assert!(!chunk.is_empty());
+    }
+
     #[test]
     fn test_chunk_string() {
         let chunk = testing_chunk();
@@ -194,12 +406,54 @@ mod tests {
         assert_eq!(chunk_string, expected_chunk_string);
     }
 
+    #[test]
+    fn test_chunk_lossy_string() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let data = [b"Hello ".as_slice(), &[0xF0, 0x28, 0x8C], b" World"].concat();
+        let chunk = Chunk::new(chunk_type, data);
+
+        assert!(chunk.data_as_string().is_err());
+
+        let lossy = chunk.data_as_lossy_string();
+        assert!(lossy.starts_with("Hello "));
+        assert!(lossy.ends_with(" World"));
+        assert!(lossy.contains(char::REPLACEMENT_CHARACTER));
+    }
+
+    #[test]
+    fn test_chunk_display_lossy() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let data = [b"Hello ".as_slice(), &[0xF0, 0x28, 0x8C], b" World"].concat();
+        let chunk = Chunk::new(chunk_type, data);
+
+        assert!(chunk.to_string().contains("Data: Not String Representable"));
+        let lossy = chunk.display_lossy().to_string();
+        assert!(lossy.contains(&format!("Data: {}", chunk.data_as_lossy_string())));
+    }
+
+    #[test]
+    fn test_chunk_latin1() {
+        let chunk = Chunk::new(ChunkType::TEXT, b"Title\0Caf\xe9 cr\xe8me".to_vec());
+
+        assert!(chunk.data_as_string().is_err());
+        assert_eq!(chunk.data_as_latin1(), "Title\0Café crème");
+        assert!(chunk.to_string().contains("Data: Title\0Café crème"));
+        assert!(chunk.to_string().contains("Type: tEXt [anc,pub,copy]"));
+    }
+
     #[test]
     fn test_chunk_crc() {
         let chunk = testing_chunk();
         assert_eq!(chunk.crc(), 2882656334);
     }
 
+    #[test]
+    fn test_chunk_crc_matches() {
+        let chunk = testing_chunk();
+        assert!(chunk.crc_matches(2882656334));
+        assert!(!chunk.crc_matches(2882656333));
+    }
+
     #[test]
     fn test_valid_chunk_from_bytes() {
         let data_length: u32 = 42;
@@ -248,6 +502,129 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    fn test_chunk_from_bytes_huge_length() {
+        let chunk_data = [&u32::MAX.to_be_bytes()[..], b"RuSt", b"tiny", &[0; 4]].concat();
+
+        let chunk = Chunk::try_from(chunk_data.as_ref());
+
+        let Err(ChunkError::InsufficientBytes { needed, available }) = chunk else {
+            panic!("Expected insufficient bytes, got {:?}", chunk);
+        };
+        assert_eq!(available, 16);
+        assert_eq!(
+            needed,
+            (u32::MAX as usize).saturating_add(CHUNK_METADATA_NUM_BYTES)
+        );
+        assert!(chunk
+            .unwrap_err()
+            .to_string()
+            .contains("Given 16 bytes are insufficient"));
+    }
+
+    #[test]
+    fn test_chunk_from_bytes_truncated_crc() {
+        let chunk_bytes = testing_chunk().as_bytes();
+
+        // Cutting into the CRC leaves enough bytes for the data, but not the
+        // whole chunk.
+        let chunk = Chunk::try_from(&chunk_bytes[..chunk_bytes.len() - 2]);
+
+        assert!(matches!(
+            chunk,
+            Err(ChunkError::InsufficientBytes {
+                needed: 54,
+                available: 52
+            })
+        ));
+    }
+
+    #[test]
+    fn test_chunk_try_from_exact() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"Hidden".to_vec());
+        let bytes = chunk.as_bytes();
+        assert_eq!(Chunk::try_from_exact(&bytes).unwrap(), chunk);
+
+        let trailing: Vec<u8> = bytes.iter().chain(b"extra").copied().collect();
+        assert_eq!(Chunk::try_from(trailing.as_ref()).unwrap(), chunk);
+        assert!(matches!(
+            Chunk::try_from_exact(&trailing),
+            Err(ChunkError::TrailingData(5))
+        ));
+    }
+
+    #[test]
+    fn test_parse_many() {
+        let stream: Vec<u8> = ["IHDR", "RuSt", "IEND"]
+            .iter()
+            .flat_map(|chunk_type| {
+                Chunk::new_from_str(ChunkType::from_str(chunk_type).unwrap(), "data").as_bytes()
+            })
+            .collect();
+
+        let chunks = Chunk::parse_many(&stream).unwrap();
+        let types: Vec<String> = chunks
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["IHDR", "RuSt", "IEND"]);
+    }
+
+    #[test]
+    fn test_parse_many_error_offset() {
+        let mut stream = testing_chunk().as_bytes();
+        stream.extend(b"\0\0\0\x01Ru");
+
+        let result = Chunk::parse_many(&stream);
+        assert!(matches!(
+            result,
+            Err(ChunkError::AtOffset { offset: 54, .. })
+        ));
+    }
+
+    #[test]
+    fn test_chunk_data_preview() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), vec![0xAB; 10]);
+        assert_eq!(chunk.data_preview(4), "abababab…");
+        assert_eq!(chunk.data_preview(10), "abababababababababab");
+        assert_eq!(chunk.data_preview(64), "abababababababababab");
+    }
+
+    #[test]
+    fn test_chunk_entropy() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+
+        assert_eq!(Chunk::new(chunk_type, Vec::new()).entropy(), 0.0);
+        assert_eq!(Chunk::new(chunk_type, vec![7; 32]).entropy(), 0.0);
+        assert_eq!(Chunk::new(chunk_type, vec![0, 1, 0, 1]).entropy(), 1.0);
+        assert_eq!(Chunk::new(chunk_type, (0..=255).collect()).entropy(), 8.0);
+    }
+
+    #[test]
+    fn test_chunk_clone_eq() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"Hidden".to_vec());
+        let cloned = chunk.clone();
+
+        assert_eq!(chunk, cloned);
+        assert_ne!(
+            chunk,
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"Other".to_vec())
+        );
+        assert_ne!(
+            chunk,
+            Chunk::new(ChunkType::from_str("RuSt").unwrap(), b"Hidden".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_chunk_debug() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), vec![0; 100]);
+        let debug = format!("{:?}", chunk);
+
+        assert!(debug.contains("chunk_type: \"ruSt\""));
+        assert!(debug.contains(&format!("data: \"{}…\"", "00".repeat(16))));
+    }
+
     #[test]
     fn test_valid_chunk_as_bytes() {
         let data_length: u32 = 42;
@@ -266,7 +643,7 @@ mod tests {
 
         let chunk = Chunk::try_from(chunk_data.as_ref()).unwrap();
 
-        let chunk_bytes = Vec::from(chunk.as_bytes());
+        let chunk_bytes = chunk.as_bytes();
 
         assert_eq!(chunk_bytes, chunk_data);
     }
@@ -291,4 +668,25 @@ mod tests {
 
         let _chunk_string = format!("{}", chunk);
     }
+
+    #[test]
+    fn test_chunk_total_bytes() {
+        let chunk = testing_chunk();
+        assert_eq!(chunk.total_bytes(), chunk.as_bytes().len());
+        assert_eq!(chunk.total_bytes(), 42 + CHUNK_METADATA_NUM_BYTES);
+    }
+
+    #[test]
+    fn test_chunk_set_data() {
+        let mut chunk = testing_chunk();
+        chunk.set_data(b"A different secret".to_vec());
+
+        let expected = Chunk::new(
+            ChunkType::from_str("RuSt").unwrap(),
+            b"A different secret".to_vec(),
+        );
+        assert_eq!(chunk.length(), expected.length());
+        assert_eq!(chunk.crc(), expected.crc());
+        assert_eq!(chunk, expected);
+    }
 }
@@ -1,20 +1,61 @@
+use rand::Rng;
 use std::fmt;
+use std::ops::Index;
 use std::str::FromStr;
 use thiserror::Error;
 
 pub const CHUNK_TYPE_NUM_BYTES: usize = 4;
 type ChunkBytes = [u8; CHUNK_TYPE_NUM_BYTES];
 
-#[derive(PartialEq, Eq, Debug)]
+// Chunk types defined by the PNG specification, including the APNG extension.
+const STANDARD_CHUNK_TYPES: [&ChunkBytes; 25] = [
+    b"IHDR", b"PLTE", b"IDAT", b"IEND", b"acTL", b"cHRM", b"cICP", b"gAMA", b"iCCP", b"mDCV",
+    b"cLLI", b"sBIT", b"sRGB", b"bKGD", b"hIST", b"tRNS", b"eXIf", b"fcTL", b"pHYs", b"sPLT",
+    b"fdAT", b"tIME", b"iTXt", b"tEXt", b"zTXt",
+];
+
+// Standard chunk types which may appear at most once in a file.
+const UNIQUE_CHUNK_TYPES: [&ChunkBytes; 18] = [
+    b"IHDR", b"PLTE", b"IEND", b"acTL", b"cHRM", b"cICP", b"gAMA", b"iCCP", b"mDCV", b"cLLI",
+    b"sBIT", b"sRGB", b"bKGD", b"hIST", b"tRNS", b"eXIf", b"pHYs", b"tIME",
+];
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
 pub struct ChunkType {
     data: ChunkBytes,
 }
 
 impl ChunkType {
+    // The spec-defined types used most often, ready made, so they can be used
+    // in match arms without going through fallible parsing.
+    pub const IHDR: ChunkType = ChunkType { data: *b"IHDR" };
+    #[allow(dead_code)]
+    pub const PLTE: ChunkType = ChunkType { data: *b"PLTE" };
+    #[allow(dead_code)]
+    pub const IDAT: ChunkType = ChunkType { data: *b"IDAT" };
+    pub const IEND: ChunkType = ChunkType { data: *b"IEND" };
+    pub const TEXT: ChunkType = ChunkType { data: *b"tEXt" };
+    pub const ZTXT: ChunkType = ChunkType { data: *b"zTXt" };
+    pub const ITXT: ChunkType = ChunkType { data: *b"iTXt" };
+
+    pub fn new(bytes: ChunkBytes) -> Result<ChunkType, ChunkTypeError> {
+        if ChunkType::are_valid_bytes(bytes) {
+            Ok(Self { data: bytes })
+        } else {
+            Err(ChunkTypeError::NonAlphabetic)
+        }
+    }
+
     pub fn bytes(&self) -> [u8; 4] {
         self.data
     }
 
+    // Always 4, for code treating chunk types like any other byte sequence.
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
     fn are_valid_bytes(bytes: [u8; 4]) -> bool {
         bytes
             .iter()
@@ -33,12 +74,10 @@ impl ChunkType {
         ChunkType::are_valid_bytes(self.data) && Self::is_reserved_bit_valid(self)
     }
 
-    #[allow(dead_code)]
     pub fn is_critical(&self) -> bool {
         !Self::is_bit_five_high(self.data[0])
     }
 
-    #[allow(dead_code)]
     pub fn is_public(&self) -> bool {
         !Self::is_bit_five_high(self.data[1])
     }
@@ -48,29 +87,169 @@ impl ChunkType {
         !Self::is_bit_five_high(self.data[2])
     }
 
-    #[allow(dead_code)]
     pub fn is_safe_to_copy(&self) -> bool {
         Self::is_bit_five_high(self.data[3])
     }
+
+    pub fn is_standard(&self) -> bool {
+        STANDARD_CHUNK_TYPES.contains(&&self.data)
+    }
+
+    pub fn must_be_unique(&self) -> bool {
+        UNIQUE_CHUNK_TYPES.contains(&&self.data)
+    }
+
+    // Generates a type which is ancillary, private, reserved bit valid and
+    // safe to copy, so decoders will skip it without complaint.
+    pub fn random_ancillary<R: Rng + ?Sized>(rng: &mut R) -> ChunkType {
+        let mut data = [0; CHUNK_TYPE_NUM_BYTES];
+        for byte in data.iter_mut() {
+            *byte = rng.gen_range(b'a'..=b'z');
+        }
+        data[2].make_ascii_uppercase();
+
+        Self { data }
+    }
+
+    // A compact summary of what the letter casing says about the chunk, e.g.
+    // "[anc,priv,copy]" for a type such as ruSt.
+    pub fn flags(&self) -> String {
+        format!(
+            "[{},{},{}]",
+            if self.is_critical() { "crit" } else { "anc" },
+            if self.is_public() { "pub" } else { "priv" },
+            if self.is_safe_to_copy() {
+                "copy"
+            } else {
+                "nocopy"
+            }
+        )
+    }
+
+    #[allow(dead_code)]
+    pub fn describe_case(&self) -> String {
+        // (position, meaning when lowercase, meaning when uppercase)
+        let meanings = [
+            ("first", "ancillary", "critical"),
+            ("second", "private", "public"),
+            ("third", "reserved bit invalid", "reserved bit valid"),
+            ("fourth", "safe to copy", "unsafe to copy"),
+        ];
+
+        meanings
+            .iter()
+            .zip(self.data)
+            .map(|(&(position, lowercase, uppercase), byte)| {
+                if Self::is_bit_five_high(byte) {
+                    format!("{} letter lowercase → {}", position, lowercase)
+                } else {
+                    format!("{} letter uppercase → {}", position, uppercase)
+                }
+            })
+            .collect::<Vec<String>>()
+            .join("; ")
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum ChunkTypeError {
     #[error("Can't construct chunk type from non alphabetic ascii characters.")]
     NonAlphabetic,
+    #[error(
+        "Can't construct chunk type from non-ASCII character {0:?}, chunk types must be ASCII."
+    )]
+    NonAscii(char),
     #[error("Bytes are of invalid length: {0}, expected {}", CHUNK_TYPE_NUM_BYTES)]
     InvalidLength(usize),
 }
 
+impl ChunkTypeError {
+    // Explains what's wrong with the `input` which caused this error, and
+    // suggests a fix where one is obvious.
+    pub fn guidance(&self, input: &str) -> String {
+        let problem = match self {
+            ChunkTypeError::InvalidLength(len) => format!("{:?} is {} bytes long", input, len),
+            ChunkTypeError::NonAscii(c) => {
+                let position = input
+                    .chars()
+                    .position(|other| other == *c)
+                    .unwrap_or_default();
+                format!(
+                    "{:?} contains the non-ASCII character {:?} at position {}",
+                    input,
+                    c,
+                    position + 1
+                )
+            }
+            ChunkTypeError::NonAlphabetic => {
+                match input.chars().position(|c| !c.is_ascii_alphabetic()) {
+                    Some(idx) => {
+                        let c = input.chars().nth(idx).unwrap_or_default();
+                        let kind = if c.is_ascii_digit() {
+                            "digit"
+                        } else {
+                            "character"
+                        };
+                        format!(
+                            "{:?} contains the {} {:?} at position {}",
+                            input,
+                            kind,
+                            c,
+                            idx + 1
+                        )
+                    }
+                    None => format!("{:?} contains a non-letter", input),
+                }
+            }
+        };
+
+        let mut guidance = format!(
+            "chunk types must be exactly {} ASCII letters, but {}",
+            CHUNK_TYPE_NUM_BYTES, problem
+        );
+        if let Some(suggestion) = suggest_chunk_type(input) {
+            guidance.push_str(&format!("; did you mean {:?}?", suggestion));
+        }
+        guidance
+    }
+}
+
+// Digits commonly typed in place of the letter they resemble.
+fn lookalike_letter(c: char) -> Option<char> {
+    match c {
+        '0' => Some('O'),
+        '1' => Some('l'),
+        '3' => Some('E'),
+        '4' => Some('A'),
+        '5' => Some('S'),
+        '6' => Some('G'),
+        '7' => Some('T'),
+        '8' => Some('B'),
+        _ => None,
+    }
+}
+
+fn suggest_chunk_type(input: &str) -> Option<String> {
+    let suggestion: String = input
+        .chars()
+        .filter_map(|c| {
+            if c.is_ascii_alphabetic() {
+                Some(c)
+            } else {
+                lookalike_letter(c)
+            }
+        })
+        .take(CHUNK_TYPE_NUM_BYTES)
+        .collect();
+
+    (suggestion.len() == CHUNK_TYPE_NUM_BYTES && suggestion != input).then_some(suggestion)
+}
+
 impl TryFrom<[u8; 4]> for ChunkType {
     type Error = ChunkTypeError;
 
     fn try_from(value: [u8; 4]) -> Result<Self, Self::Error> {
-        if ChunkType::are_valid_bytes(value) {
-            Ok(Self { data: value })
-        } else {
-            Err(ChunkTypeError::NonAlphabetic)
-        }
+        ChunkType::new(value)
     }
 }
 
@@ -78,12 +257,36 @@ impl FromStr for ChunkType {
     type Err = ChunkTypeError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Checked before the length, as a multi-byte character would otherwise
+        // be reported as the wrong number of bytes.
+        if let Some(c) = s.chars().find(|c| !c.is_ascii()) {
+            return Err(ChunkTypeError::NonAscii(c));
+        }
+
         let chunk_bytes: ChunkBytes = s
             .as_bytes()
             .try_into()
             .map_err(|_| ChunkTypeError::InvalidLength(s.len()))?;
 
-        ChunkType::try_from(chunk_bytes)
+        ChunkType::new(chunk_bytes)
+    }
+}
+
+// Parses one chunk type per line, ignoring blank lines.
+pub fn parse_chunk_type_list(list: &str) -> Result<Vec<ChunkType>, ChunkTypeError> {
+    list.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(ChunkType::from_str)
+        .collect()
+}
+
+// Panics on an out of bounds index, as with a slice.
+impl Index<usize> for ChunkType {
+    type Output = u8;
+
+    fn index(&self, index: usize) -> &u8 {
+        &self.data[index]
     }
 }
 
@@ -107,6 +310,18 @@ mod tests {
         assert_eq!(expected, actual.bytes());
     }
 
+    #[test]
+    pub fn test_chunk_type_new() {
+        let new = ChunkType::new(*b"RuSt").unwrap();
+        let try_from = ChunkType::try_from(*b"RuSt").unwrap();
+        assert_eq!(new, try_from);
+
+        let new = ChunkType::new(*b"Ru1t");
+        let try_from = ChunkType::try_from(*b"Ru1t");
+        assert!(matches!(new, Err(ChunkTypeError::NonAlphabetic)));
+        assert!(matches!(try_from, Err(ChunkTypeError::NonAlphabetic)));
+    }
+
     #[test]
     pub fn test_chunk_type_from_str() {
         let expected = ChunkType::try_from([82, 117, 83, 116]).unwrap();
@@ -162,6 +377,20 @@ mod tests {
         assert!(!chunk.is_safe_to_copy());
     }
 
+    #[test]
+    pub fn test_chunk_type_flags() {
+        assert_eq!(ChunkType::IHDR.flags(), "[crit,pub,nocopy]");
+        assert_eq!(ChunkType::TEXT.flags(), "[anc,pub,copy]");
+        assert_eq!(
+            ChunkType::from_str("ruSt").unwrap().flags(),
+            "[anc,priv,copy]"
+        );
+        assert_eq!(
+            ChunkType::from_str("RuST").unwrap().flags(),
+            "[crit,priv,nocopy]"
+        );
+    }
+
     #[test]
     pub fn test_valid_chunk_is_valid() {
         let chunk = ChunkType::from_str("RuSt").unwrap();
@@ -177,12 +406,175 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    pub fn test_chunk_type_is_standard() {
+        let chunk = ChunkType::from_str("IHDR").unwrap();
+        assert!(chunk.is_standard());
+
+        let chunk = ChunkType::from_str("tEXt").unwrap();
+        assert!(chunk.is_standard());
+    }
+
+    #[test]
+    pub fn test_chunk_type_constants() {
+        let constants = [
+            (ChunkType::IHDR, "IHDR"),
+            (ChunkType::PLTE, "PLTE"),
+            (ChunkType::IDAT, "IDAT"),
+            (ChunkType::IEND, "IEND"),
+            (ChunkType::TEXT, "tEXt"),
+            (ChunkType::ZTXT, "zTXt"),
+            (ChunkType::ITXT, "iTXt"),
+        ];
+
+        for (constant, name) in constants {
+            assert_eq!(constant, ChunkType::from_str(name).unwrap());
+            assert!(constant.is_valid());
+            assert!(constant.is_standard());
+        }
+    }
+
+    #[test]
+    pub fn test_chunk_type_must_be_unique() {
+        assert!(ChunkType::from_str("IHDR").unwrap().must_be_unique());
+        assert!(ChunkType::from_str("gAMA").unwrap().must_be_unique());
+        assert!(!ChunkType::from_str("IDAT").unwrap().must_be_unique());
+        assert!(!ChunkType::from_str("tEXt").unwrap().must_be_unique());
+        assert!(!ChunkType::from_str("ruSt").unwrap().must_be_unique());
+    }
+
+    #[test]
+    pub fn test_chunk_type_random_ancillary() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..50 {
+            let chunk = ChunkType::random_ancillary(&mut rng);
+            assert!(chunk.is_valid());
+            assert!(!chunk.is_critical());
+            assert!(!chunk.is_public());
+            assert!(chunk.is_safe_to_copy());
+        }
+    }
+
+    #[test]
+    pub fn test_chunk_type_is_not_standard() {
+        let chunk = ChunkType::from_str("RuSt").unwrap();
+        assert!(!chunk.is_standard());
+
+        let chunk = ChunkType::from_str("TEXT").unwrap();
+        assert!(!chunk.is_standard());
+    }
+
+    #[test]
+    pub fn test_chunk_type_describe_case() {
+        let chunk = ChunkType::from_str("RuSt").unwrap();
+        assert_eq!(
+            chunk.describe_case(),
+            "first letter uppercase → critical; \
+             second letter lowercase → private; \
+             third letter uppercase → reserved bit valid; \
+             fourth letter lowercase → safe to copy"
+        );
+
+        let chunk = ChunkType::from_str("bLub").unwrap();
+        let description = chunk.describe_case();
+        assert!(description.contains("ancillary"));
+        assert!(description.contains("public"));
+        assert!(description.contains("reserved bit invalid"));
+    }
+
+    #[test]
+    pub fn test_chunk_type_guidance_digit() {
+        let err = ChunkType::from_str("ru5t").unwrap_err();
+        assert_eq!(
+            err.guidance("ru5t"),
+            "chunk types must be exactly 4 ASCII letters, but \"ru5t\" contains the digit '5' \
+             at position 3; did you mean \"ruSt\"?"
+        );
+    }
+
+    #[test]
+    pub fn test_chunk_type_guidance_length() {
+        let err = ChunkType::from_str("RuStacean").unwrap_err();
+        assert_eq!(
+            err.guidance("RuStacean"),
+            "chunk types must be exactly 4 ASCII letters, but \"RuStacean\" is 9 bytes long; \
+             did you mean \"RuSt\"?"
+        );
+
+        let err = ChunkType::from_str("Rs").unwrap_err();
+        assert_eq!(
+            err.guidance("Rs"),
+            "chunk types must be exactly 4 ASCII letters, but \"Rs\" is 2 bytes long"
+        );
+    }
+
+    #[test]
+    pub fn test_chunk_type_from_str_non_ascii() {
+        // Five bytes long, but the problem is the accent.
+        let err = ChunkType::from_str("café").unwrap_err();
+        assert!(matches!(err, ChunkTypeError::NonAscii('é')));
+        assert_eq!(
+            err.guidance("café"),
+            "chunk types must be exactly 4 ASCII letters, but \"café\" contains the non-ASCII \
+             character 'é' at position 4"
+        );
+
+        let err = ChunkType::from_str("RuStacéan").unwrap_err();
+        assert!(matches!(err, ChunkTypeError::NonAscii('é')));
+
+        let err = ChunkType::from_str("Ru-t").unwrap_err();
+        assert!(matches!(err, ChunkTypeError::NonAlphabetic));
+    }
+
+    #[test]
+    pub fn test_parse_chunk_type_list() {
+        let chunk_types = parse_chunk_type_list("tEXt\n\n  RuSt \n").unwrap();
+        assert_eq!(
+            chunk_types,
+            vec![
+                ChunkType::from_str("tEXt").unwrap(),
+                ChunkType::from_str("RuSt").unwrap()
+            ]
+        );
+
+        assert!(parse_chunk_type_list("tEXt\nRu5t").is_err());
+    }
+
     #[test]
     pub fn test_chunk_type_string() {
         let chunk = ChunkType::from_str("RuSt").unwrap();
         assert_eq!(&chunk.to_string(), "RuSt");
     }
 
+    #[test]
+    pub fn test_chunk_type_copy() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let copied = chunk_type;
+
+        assert_eq!(chunk_type, copied);
+        assert_eq!(chunk_type.to_string(), copied.to_string());
+    }
+
+    #[test]
+    pub fn test_chunk_type_index() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+
+        assert_eq!(chunk_type.len(), 4);
+        assert_eq!(chunk_type[0], b'R');
+        assert_eq!(chunk_type[1], b'u');
+        assert_eq!(chunk_type[2], b'S');
+        assert_eq!(chunk_type[3], b't');
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn test_chunk_type_index_out_of_bounds() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let _ = chunk_type[4];
+    }
+
     #[test]
     pub fn test_chunk_type_trait_impls() {
         let chunk_type_1: ChunkType = TryFrom::try_from([82, 117, 83, 116]).unwrap();
@@ -0,0 +1,166 @@
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use std::io::{self, Read, Write};
+use thiserror::Error;
+
+const RECORD_LENGTH_NUM_BYTES: usize = 4;
+
+#[derive(Error, Debug)]
+pub enum CodecError {
+    #[error(
+        "Record at offset {offset} is cut short, expected {expected} bytes but {remaining} remain."
+    )]
+    TruncatedRecord {
+        offset: usize,
+        expected: usize,
+        remaining: usize,
+    },
+}
+
+// Somewhere between fast and small, matching zlib's own default.
+pub const DEFAULT_COMPRESS_LEVEL: u32 = 6;
+
+#[allow(dead_code)]
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    deflate_with_level(data, DEFAULT_COMPRESS_LEVEL)
+}
+
+// Level 0 only stores the data, while 9 compresses it as far as possible.
+pub fn deflate_with_level(data: &[u8], level: u32) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(level));
+    encoder
+        .write_all(data)
+        .and_then(|_| encoder.finish())
+        .expect("Writing into memory can't fail")
+}
+
+pub fn inflate(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut inflated = Vec::new();
+    ZlibDecoder::new(data).read_to_end(&mut inflated)?;
+    Ok(inflated)
+}
+
+// Packs several messages into one buffer, each as a big-endian u32 length
+// followed by that many bytes.
+pub fn pack_messages<T: AsRef<[u8]>>(messages: &[T]) -> Vec<u8> {
+    let mut packed = Vec::new();
+    for message in messages {
+        let message = message.as_ref();
+        packed.extend((message.len() as u32).to_be_bytes());
+        packed.extend(message);
+    }
+    packed
+}
+
+pub fn unpack_messages(bytes: &[u8]) -> Result<Vec<Vec<u8>>, CodecError> {
+    let mut messages = Vec::new();
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let remaining = bytes.len() - offset;
+        let length_bytes: [u8; RECORD_LENGTH_NUM_BYTES] = bytes
+            .get(offset..offset + RECORD_LENGTH_NUM_BYTES)
+            .and_then(|slice| slice.try_into().ok())
+            .ok_or(CodecError::TruncatedRecord {
+                offset,
+                expected: RECORD_LENGTH_NUM_BYTES,
+                remaining,
+            })?;
+        let length = u32::from_be_bytes(length_bytes) as usize;
+
+        let start = offset + RECORD_LENGTH_NUM_BYTES;
+        let message =
+            bytes
+                .get(start..start.saturating_add(length))
+                .ok_or(CodecError::TruncatedRecord {
+                    offset,
+                    expected: RECORD_LENGTH_NUM_BYTES + length,
+                    remaining,
+                })?;
+
+        messages.push(message.to_vec());
+        offset = start + length;
+    }
+
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deflate_higher_level_no_larger() {
+        let data = "All work and no play makes Jack a dull boy. ".repeat(200);
+
+        let fast = deflate_with_level(data.as_bytes(), 1);
+        let small = deflate_with_level(data.as_bytes(), 9);
+
+        assert!(small.len() <= fast.len());
+        assert!(fast.len() < data.len());
+    }
+
+    #[test]
+    fn test_inflate_round_trip() {
+        let data = b"Hidden message, hidden message, hidden message";
+        let inflated = inflate(&deflate_with_level(data, DEFAULT_COMPRESS_LEVEL)).unwrap();
+        assert_eq!(inflated, data);
+    }
+
+    #[test]
+    fn test_deflate_default_level() {
+        let data = b"Hidden message, hidden message, hidden message";
+        assert_eq!(
+            deflate(data),
+            deflate_with_level(data, DEFAULT_COMPRESS_LEVEL)
+        );
+        assert_eq!(inflate(&deflate(data)).unwrap(), data);
+    }
+
+    #[test]
+    fn test_inflate_uncompressed() {
+        assert!(inflate(b"Not compressed at all").is_err());
+    }
+
+    #[test]
+    fn test_pack_messages_round_trip() {
+        let messages = ["first", "", "third message"];
+        let packed = pack_messages(&messages);
+        assert_eq!(packed.len(), 3 * RECORD_LENGTH_NUM_BYTES + 18);
+
+        let unpacked = unpack_messages(&packed).unwrap();
+        assert_eq!(
+            unpacked,
+            vec![b"first".to_vec(), Vec::new(), b"third message".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_unpack_messages_truncated() {
+        let mut packed = pack_messages(&["first", "second"]);
+        packed.truncate(packed.len() - 1);
+
+        let result = unpack_messages(&packed);
+        assert!(matches!(
+            result,
+            Err(CodecError::TruncatedRecord {
+                offset: 9,
+                expected: 10,
+                remaining: 9
+            })
+        ));
+
+        let result = unpack_messages(&[0, 0]);
+        assert!(matches!(
+            result,
+            Err(CodecError::TruncatedRecord { offset: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_deflate_level_zero_stores() {
+        let data = [7; 1000];
+        let stored = deflate_with_level(&data, 0);
+
+        assert!(stored.len() >= data.len());
+    }
+}
@@ -7,24 +7,348 @@ use clap::{Parser, Subcommand};
 pub struct Args {
     #[command(subcommand)]
     pub cmd: Commands,
+
+    #[command(flatten)]
+    pub read: ReadOptions,
+
+    /// Only print what was asked for, and errors, not status messages
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ReadOptions {
+    /// Parse from the first PNG signature found in the input, rather than
+    /// requiring it at the very start
+    #[arg(long, global = true)]
+    pub scan_offset: bool,
+
+    /// Treat the input as a bare chunk stream with no PNG signature
+    #[arg(long, global = true, conflicts_with = "scan_offset")]
+    pub no_signature: bool,
+
+    /// Report the N chunks which took longest to parse
+    #[arg(long, global = true, value_name = "N", num_args = 0..=1, default_missing_value = "5")]
+    pub profile: Option<usize>,
+
+    /// Accept chunks whose CRC doesn't match their contents, giving them the
+    /// correct CRC if the file is written back
+    #[arg(long, global = true, conflicts_with = "profile")]
+    pub fix_crc: bool,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    Text,
+    Hex,
+    HexFlat,
+    Base64,
 }
 
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Json,
+}
+
+// Only ever built once, when parsing the arguments, so Encode's many options
+// being far larger than the other variants doesn't matter.
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug, Clone)]
 pub enum Commands {
     #[command(arg_required_else_help = true)]
     Encode {
         path: PathBuf,
         chunk_type: String,
-        message: String,
+        /// The message to hide; read from stdin if not given
+        message: Option<String>,
+        output: Option<PathBuf>,
+        /// Name the output after the input, filling in {stem} and {ext}, e.g.
+        /// "{stem}_tagged.{ext}"
+        #[arg(long, value_name = "TEMPLATE", conflicts_with = "output")]
+        output_template: Option<String>,
+        /// Pack another message into the same chunk, after MESSAGE; may be
+        /// given more than once
+        #[arg(long = "multi", value_name = "MESSAGE")]
+        multi: Vec<String>,
+        #[arg(short = 'y', long)]
+        assume_yes: bool,
+        #[arg(long)]
+        validate_input: bool,
+        /// Group the new chunk with the chunks of this type, e.g. IDAT, rather
+        /// than adding it at the end
+        #[arg(long, value_name = "TYPE", requires = "append")]
+        into: Option<String>,
+        /// Insert the new chunk straight after the last chunk of the `--into`
+        /// type
+        #[arg(long, requires = "into")]
+        append: bool,
+        /// Insert the new chunk straight after the last chunk of this type,
+        /// e.g. IHDR
+        #[arg(long, value_name = "TYPE", conflicts_with = "into")]
+        after: Option<String>,
+        /// Compress the message with zlib before storing it
+        #[arg(long)]
+        compress: bool,
+        /// 0 stores the message uncompressed, 9 compresses it the most
+        #[arg(
+            long,
+            value_name = "0-9",
+            requires = "compress",
+            default_value_t = crate::codec::DEFAULT_COMPRESS_LEVEL,
+            value_parser = clap::value_parser!(u32).range(0..=9)
+        )]
+        compress_level: u32,
+        /// Write the resulting PNG to stdout, leaving the input untouched
+        #[arg(long, conflicts_with_all = ["output", "output_template", "assume_yes"])]
+        stdout: bool,
+        /// Keep the file's modification time unchanged when overwriting it
+        #[arg(long, conflicts_with_all = ["output", "output_template", "stdout", "out_dir"])]
+        preserve_mtime: bool,
+        /// Encrypt the message with a key derived from this passphrase
+        #[arg(long, value_name = "PASSPHRASE")]
+        encrypt: Option<String>,
+        /// Treat PATH as a directory, adding the chunk to every PNG file found
+        /// in it; OUTPUT then names a directory to mirror the results into
+        #[arg(long, conflicts_with = "stdout")]
+        recursive: bool,
+        /// Store the message as the value of a tEXt keyword/value pair, so
+        /// that other PNG tools can read it
+        #[arg(long, value_name = "KEYWORD", conflicts_with_all = ["multi", "compress", "encrypt"])]
+        text_keyword: Option<String>,
+        /// Use the bytes of this file as the message, exactly as they are
+        #[arg(long, value_name = "PATH", conflicts_with = "message")]
+        data_file: Option<PathBuf>,
+        /// Write the result into this directory under the input's file name,
+        /// creating it if needed, rather than overwriting the input
+        #[arg(long, value_name = "DIR", conflicts_with_all = ["output", "output_template", "stdout"])]
+        out_dir: Option<PathBuf>,
+    },
+
+    #[command(arg_required_else_help = true)]
+    Decode {
+        path: PathBuf,
+        chunk_type: String,
+        #[arg(long)]
+        lossy: bool,
+        #[arg(long, value_enum, default_value_t = DataFormat::Text)]
+        format: DataFormat,
+        /// Wrap encoded output at N characters, 0 for no wrapping [default: 76
+        /// for base64, 0 for hex-flat, ignored for hex]
+        #[arg(long, value_name = "N")]
+        wrap: Option<usize>,
+        /// Write the chunk's data to stdout exactly as stored
+        #[arg(long, conflicts_with_all = ["lossy", "format", "wrap", "multi"])]
+        raw: bool,
+        /// Unpack several messages packed into the chunk with `encode --multi`
+        #[arg(long, conflicts_with_all = ["format", "wrap"])]
+        multi: bool,
+        /// Decode every chunk of the type, not just the first
+        #[arg(long)]
+        all: bool,
+        /// Inflate data stored with `encode --compress` before printing it
+        #[arg(long)]
+        decompress: bool,
+        /// Decrypt data stored with `encode --encrypt` using this passphrase
+        #[arg(long, value_name = "PASSPHRASE")]
+        decrypt: Option<String>,
+    },
+
+    #[command(arg_required_else_help = true)]
+    Remove {
+        path: PathBuf,
+        #[arg(
+            required_unless_present_any = ["types_file", "index"],
+            conflicts_with_all = ["types_file", "index"]
+        )]
+        chunk_type: Option<String>,
+        /// Remove every chunk whose type is listed in this file, one per line
+        #[arg(long, conflicts_with = "index")]
+        types_file: Option<PathBuf>,
+        /// Remove the chunk at index N, counting from 0 as `chunks` does
+        #[arg(long, value_name = "N")]
+        index: Option<usize>,
+        /// Remove every chunk of the type, not just the first
+        #[arg(long, requires = "chunk_type")]
+        all: bool,
+        #[arg(short = 'y', long)]
+        assume_yes: bool,
+        /// Write the resulting PNG to stdout, leaving the input untouched
+        #[arg(long, conflicts_with = "assume_yes")]
+        stdout: bool,
+        /// Keep the file's modification time unchanged when overwriting it
+        #[arg(long, conflicts_with = "stdout")]
+        preserve_mtime: bool,
+    },
+
+    #[command(arg_required_else_help = true)]
+    Copy {
+        source: PathBuf,
+        dest: PathBuf,
+        chunk_type: String,
+        /// Where to write the result; DEST is overwritten if not given
+        output: Option<PathBuf>,
+        #[arg(short = 'y', long)]
+        assume_yes: bool,
+    },
+
+    #[command(arg_required_else_help = true)]
+    #[command(group(clap::ArgGroup::new("cleanup").required(true).multiple(true)))]
+    Clean {
+        path: PathBuf,
         output: Option<PathBuf>,
+        /// Keep only the last tEXt chunk for each keyword
+        #[arg(long, group = "cleanup")]
+        dedup_text: bool,
+        /// Remove any chunks or other data after the IEND chunk
+        #[arg(long, group = "cleanup")]
+        trailing: bool,
+        #[arg(short = 'y', long)]
+        assume_yes: bool,
+    },
+
+    #[command(arg_required_else_help = true)]
+    Repair {
+        path: PathBuf,
+        output: Option<PathBuf>,
+        #[arg(short = 'y', long)]
+        assume_yes: bool,
+    },
+
+    #[command(arg_required_else_help = true)]
+    Count {
+        path: PathBuf,
+        #[arg(long)]
+        field_separator: Option<String>,
+    },
+
+    #[command(arg_required_else_help = true)]
+    ExtractTrailing {
+        path: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+
+    #[command(arg_required_else_help = true)]
+    Extract {
+        path: PathBuf,
+        /// Directory to write each chunk's data into
+        #[arg(short, long, value_name = "DIR")]
+        output: PathBuf,
+        /// Skip chunk types defined by the PNG spec (the default)
+        #[arg(long, conflicts_with = "include_standard")]
+        exclude_standard: bool,
+        /// Extract chunk types defined by the PNG spec too
+        #[arg(long)]
+        include_standard: bool,
+    },
+
+    #[command(arg_required_else_help = true)]
+    Validate {
+        path: PathBuf,
+        /// Only check each chunk against its CRC, skipping the structure checks
+        #[arg(long)]
+        checksum_only: bool,
+        /// Also check that writing the PNG back out reproduces the file
+        /// byte for byte
+        #[arg(long, conflicts_with = "checksum_only")]
+        two_pass: bool,
+        /// List chunk types appearing more than once, failing if the spec
+        /// says any must be unique
+        #[arg(long, conflicts_with = "checksum_only")]
+        report_duplicates: bool,
+        /// Also print the audit report, as text or as JSON with the verdict
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+
+    #[command(arg_required_else_help = true)]
+    Audit {
+        path: PathBuf,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
     },
 
     #[command(arg_required_else_help = true)]
-    Decode { path: PathBuf, chunk_type: String },
+    Digest {
+        /// Files to digest, or directories to search for .png files
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        /// Follow symlinks when searching directories
+        #[arg(long)]
+        follow_symlinks: bool,
+        /// Only search N directories deep, where 1 is just the files directly
+        /// inside each directory given
+        #[arg(long, value_name = "N")]
+        max_depth: Option<usize>,
+        /// Only digest the data of chunks of this type, e.g. IDAT
+        #[arg(long, value_name = "TYPE")]
+        only_type: Option<String>,
+        /// Expand glob patterns such as *.png in the paths given (always on
+        /// for Windows)
+        #[arg(long)]
+        glob: bool,
+        /// After the digests, print how often each chunk type appeared across
+        /// every file
+        #[arg(long)]
+        chunk_type_stats: bool,
+    },
+
+    #[command(arg_required_else_help = true)]
+    Chunks {
+        path: PathBuf,
+        #[arg(long)]
+        field_separator: Option<String>,
+    },
+
+    #[command(arg_required_else_help = true)]
+    Probe { path: PathBuf },
+
+    #[command(arg_required_else_help = true)]
+    Preview {
+        path: PathBuf,
+        /// The most characters wide the preview may be
+        #[arg(long, value_name = "COLUMNS", default_value_t = 64)]
+        width: usize,
+    },
 
     #[command(arg_required_else_help = true)]
-    Remove { path: PathBuf, chunk_type: String },
+    Detect {
+        path: PathBuf,
+        /// Only report chunks with more than this many bits of entropy per
+        /// byte, out of a maximum of 8
+        #[arg(long, value_name = "BITS")]
+        min_entropy: Option<f64>,
+    },
 
     #[command(arg_required_else_help = true)]
-    Print { path: PathBuf },
+    Print {
+        path: PathBuf,
+        #[arg(long)]
+        lossy: bool,
+        /// Write every chunk to stdout as bytes, without the PNG signature
+        #[arg(long, conflicts_with = "lossy")]
+        raw_chunks: bool,
+        /// Print the chunks as a JSON array, with their data base64 encoded
+        #[arg(long, conflicts_with_all = ["lossy", "raw_chunks"])]
+        json: bool,
+    },
+}
+
+impl Commands {
+    // Status messages go to stderr instead when stdout carries the PNG.
+    pub fn writes_png_to_stdout(&self) -> bool {
+        match self {
+            Commands::Encode { stdout: true, .. } | Commands::Remove { stdout: true, .. } => true,
+            Commands::Encode {
+                output: Some(output),
+                ..
+            }
+            | Commands::Copy {
+                output: Some(output),
+                ..
+            } => crate::output::is_stdout_path(output),
+            _ => false,
+        }
+    }
 }
@@ -0,0 +1,98 @@
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rand::RngCore;
+use thiserror::Error;
+
+const SALT_NUM_BYTES: usize = 16;
+const NONCE_NUM_BYTES: usize = 12;
+
+#[derive(Error, Debug)]
+pub enum CryptoError {
+    #[error(
+        "Encrypted data should be at least {} bytes but was {0}.",
+        SALT_NUM_BYTES + NONCE_NUM_BYTES
+    )]
+    Truncated(usize),
+    #[error("Decryption failed, either the passphrase is wrong or the data isn't encrypted.")]
+    Failed,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Key {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .expect("Salt and key lengths are fixed and valid");
+    key
+}
+
+// The salt and nonce are stored ahead of the ciphertext, so the result can be
+// decrypted with nothing but the passphrase.
+pub fn encrypt(message: &[u8], passphrase: &str) -> Vec<u8> {
+    let mut salt = [0; SALT_NUM_BYTES];
+    let mut nonce = [0; NONCE_NUM_BYTES];
+    let mut rng = rand::thread_rng();
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut nonce);
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), message)
+        .expect("Messages are far below the cipher's size limit");
+
+    [&salt[..], &nonce[..], &ciphertext].concat()
+}
+
+// The ciphertext is authenticated, so a wrong passphrase is an error rather
+// than garbage.
+pub fn decrypt(blob: &[u8], passphrase: &str) -> Result<Vec<u8>, CryptoError> {
+    if blob.len() < SALT_NUM_BYTES + NONCE_NUM_BYTES {
+        return Err(CryptoError::Truncated(blob.len()));
+    }
+
+    let (salt, rest) = blob.split_at(SALT_NUM_BYTES);
+    let (nonce, ciphertext) = rest.split_at(NONCE_NUM_BYTES);
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, salt));
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| CryptoError::Failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let message = b"Hidden message";
+        let blob = encrypt(message, "correct horse");
+
+        assert_ne!(&blob[SALT_NUM_BYTES + NONCE_NUM_BYTES..], message);
+        assert_eq!(decrypt(&blob, "correct horse").unwrap(), message);
+    }
+
+    #[test]
+    fn test_encrypt_salts_each_message() {
+        assert_ne!(encrypt(b"Hidden", "pass"), encrypt(b"Hidden", "pass"));
+    }
+
+    #[test]
+    fn test_decrypt_wrong_passphrase() {
+        let blob = encrypt(b"Hidden message", "correct horse");
+        assert!(matches!(
+            decrypt(&blob, "battery staple"),
+            Err(CryptoError::Failed)
+        ));
+    }
+
+    #[test]
+    fn test_decrypt_truncated() {
+        assert!(matches!(
+            decrypt(&[0; 10], "pass"),
+            Err(CryptoError::Truncated(10))
+        ));
+    }
+}
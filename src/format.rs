@@ -0,0 +1,621 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::{
+    audit::{AuditReport, DuplicateType},
+    chunk::Chunk,
+    chunk_type::ChunkType,
+    png::Png,
+};
+
+const NON_STANDARD_MARKER: &str = "(non-standard)";
+const MUST_BE_UNIQUE_MARKER: &str = "(must be unique)";
+
+const DEFAULT_SEPARATOR: &str = " ";
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Conventional MIME line length for base64 text.
+pub const BASE64_WRAP_WIDTH: usize = 76;
+
+pub fn to_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for group in bytes.chunks(3) {
+        let padded = [
+            group[0],
+            group.get(1).copied().unwrap_or(0),
+            group.get(2).copied().unwrap_or(0),
+        ];
+        let bits = u32::from_be_bytes([0, padded[0], padded[1], padded[2]]);
+
+        for idx in 0..4 {
+            if idx <= group.len() {
+                let sextet = (bits >> (18 - 6 * idx)) & 0x3F;
+                encoded.push(char::from(BASE64_ALPHABET[sextet as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+const HEX_DUMP_LINE_BYTES: usize = 16;
+
+// Lays bytes out as `xxd` does: the offset, then sixteen bytes in groups of
+// two, then the same bytes as ASCII with anything unprintable shown as '.'.
+pub fn hex_dump(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .chunks(HEX_DUMP_LINE_BYTES)
+        .enumerate()
+        .map(|(line, group)| {
+            let hex = group
+                .chunks(2)
+                .map(to_hex)
+                .collect::<Vec<String>>()
+                .join(" ");
+            let ascii: String = group
+                .iter()
+                .map(|&byte| match byte {
+                    b' '..=b'~' => byte as char,
+                    _ => '.',
+                })
+                .collect();
+            // Every full line's hex column is 39 characters wide.
+            format!("{:08x}: {:<39}  {}", line * HEX_DUMP_LINE_BYTES, hex, ascii)
+        })
+        .collect()
+}
+
+// Breaks text into lines of at most `width` characters. A width of zero leaves
+// the text on a single line.
+pub fn wrap(text: &str, width: usize) -> String {
+    if width == 0 {
+        return text.to_string();
+    }
+
+    text.chars()
+        .collect::<Vec<char>>()
+        .chunks(width)
+        .map(|line| line.iter().collect::<String>())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Without a custom separator the columns are padded to line up, with numeric
+// columns right-aligned so their digits line up too.
+pub fn format_rows(rows: &[Vec<String>], separator: Option<&str>) -> Vec<String> {
+    if let Some(separator) = separator {
+        return rows.iter().map(|row| row.join(separator)).collect();
+    }
+
+    let num_columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let columns: Vec<(usize, bool)> = (0..num_columns)
+        .map(|idx| {
+            let cells = rows.iter().filter_map(|row| row.get(idx));
+            let width = cells.clone().map(|cell| cell.chars().count()).max();
+            let is_numeric = cells
+                .filter(|cell| !cell.is_empty())
+                .all(|cell| cell.chars().all(|c| c.is_ascii_digit()));
+            (width.unwrap_or(0), is_numeric)
+        })
+        .collect();
+
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&columns)
+                .map(|(cell, &(width, is_numeric))| {
+                    if is_numeric {
+                        format!("{:>width$}", cell, width = width)
+                    } else {
+                        format!("{:<width$}", cell, width = width)
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join(DEFAULT_SEPARATOR)
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+// A custom separator is meant for machine consumption, so the summary line is
+// left off to keep every line the same shape.
+pub fn format_counts(png: &Png, separator: Option<&str>) -> Vec<String> {
+    let counts = png.chunk_counts();
+    let mut rows = Vec::with_capacity(counts.len());
+    let mut non_standard = 0;
+
+    for (chunk_type, count) in &counts {
+        let is_standard = ChunkType::from_str(chunk_type)
+            .map(|chunk_type| chunk_type.is_standard())
+            .unwrap_or(false);
+
+        let marker = if is_standard {
+            ""
+        } else {
+            non_standard += 1;
+            NON_STANDARD_MARKER
+        };
+
+        rows.push(vec![
+            chunk_type.clone(),
+            count.to_string(),
+            marker.to_string(),
+        ]);
+    }
+
+    let mut lines = format_rows(&rows, separator);
+
+    if separator.is_none() {
+        lines.push(format!(
+            "{} distinct chunk types, {} non-standard",
+            counts.len(),
+            non_standard
+        ));
+    }
+
+    lines
+}
+
+// Chunk types with their counts, most frequent first, then alphabetically.
+pub fn format_frequency_table(counts: &BTreeMap<String, usize>) -> Vec<String> {
+    let mut sorted: Vec<(&String, &usize)> = counts.iter().collect();
+    sorted.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    let rows: Vec<Vec<String>> = sorted
+        .into_iter()
+        .map(|(chunk_type, count)| vec![chunk_type.clone(), count.to_string()])
+        .collect();
+
+    format_rows(&rows, None)
+}
+
+// One line per repeated chunk type, with its count, marking those the spec
+// says must be unique.
+pub fn format_duplicates(duplicates: &[DuplicateType]) -> Vec<String> {
+    let rows: Vec<Vec<String>> = duplicates
+        .iter()
+        .map(|duplicate| {
+            let marker = if duplicate.violation {
+                MUST_BE_UNIQUE_MARKER
+            } else {
+                ""
+            };
+            vec![
+                duplicate.chunk_type.clone(),
+                duplicate.count.to_string(),
+                marker.to_string(),
+            ]
+        })
+        .collect();
+
+    format_rows(&rows, None)
+}
+
+// One line per chunk, giving its index, byte offset in the file, type, data
+// length and CRC.
+pub fn format_chunk_list(png: &Png, separator: Option<&str>) -> Vec<String> {
+    let rows: Vec<Vec<String>> = png
+        .chunk_iter_with_offsets()
+        .enumerate()
+        .map(|(idx, (offset, chunk))| {
+            vec![
+                idx.to_string(),
+                offset.to_string(),
+                chunk.chunk_type().to_string(),
+                chunk.length().to_string(),
+                format!("{:08x}", chunk.crc()),
+            ]
+        })
+        .collect();
+
+    format_rows(&rows, separator)
+}
+
+// One line per chunk found by `detect`, giving its index, type, data length
+// and entropy in bits per byte.
+pub fn format_detected(detected: &[(usize, &Chunk)]) -> Vec<String> {
+    let rows: Vec<Vec<String>> = detected
+        .iter()
+        .map(|(idx, chunk)| {
+            vec![
+                idx.to_string(),
+                chunk.chunk_type().to_string(),
+                chunk.length().to_string(),
+                format!("{:.2}", chunk.entropy()),
+            ]
+        })
+        .collect();
+
+    format_rows(&rows, None)
+}
+
+// Lists the `count` slowest chunks to parse, slowest first.
+pub fn format_profile(png: &Png, timings: &[Duration], count: usize) -> Vec<String> {
+    let mut profile: Vec<(usize, &Duration)> = timings.iter().enumerate().collect();
+    profile.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    profile
+        .into_iter()
+        .take(count)
+        .filter_map(|(idx, duration)| {
+            let chunk = png.chunks().get(idx)?;
+            Some(format!(
+                "chunk {} {} {} bytes {:?}",
+                idx,
+                chunk.chunk_type(),
+                chunk.length(),
+                duration
+            ))
+        })
+        .collect()
+}
+
+fn list_or_none<T: ToString>(items: &[T]) -> String {
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items
+            .iter()
+            .map(|item| item.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+pub fn format_audit_text(report: &AuditReport) -> Vec<String> {
+    let mut lines = vec![
+        format!(
+            "Signature: {}",
+            if report.signature_ok { "ok" } else { "invalid" }
+        ),
+        format!("Chunks: {}", report.chunk_count),
+        format!("Bad CRCs: {}", list_or_none(&report.bad_crc_chunks)),
+        format!("Ordering issues: {}", report.ordering_issues.len()),
+    ];
+    lines.extend(
+        report
+            .ordering_issues
+            .iter()
+            .map(|issue| format!("\t{}", issue)),
+    );
+    lines.push(format!(
+        "Unknown types: {}",
+        list_or_none(&report.unknown_types)
+    ));
+    lines.push(format!("Trailing bytes: {}", report.trailing_bytes_len));
+    lines
+}
+
+fn json_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn json_array<T>(items: &[T], render: impl Fn(&T) -> String) -> String {
+    let items: Vec<String> = items.iter().map(render).collect();
+    format!("[{}]", items.join(","))
+}
+
+pub fn format_audit_json(report: &AuditReport) -> String {
+    format!(
+        "{{\"signature_ok\":{},\"chunk_count\":{},\"bad_crc_chunks\":{},\
+         \"ordering_issues\":{},\"unknown_types\":{},\"trailing_bytes_len\":{}}}",
+        report.signature_ok,
+        report.chunk_count,
+        json_array(&report.bad_crc_chunks, |idx| idx.to_string()),
+        json_array(&report.ordering_issues, |issue| json_string(issue)),
+        json_array(&report.unknown_types, |chunk_type| json_string(chunk_type)),
+        report.trailing_bytes_len
+    )
+}
+
+// The audit report alongside validate's verdict, which can also fail for
+// reasons the audit doesn't cover, such as duplicate chunks.
+pub fn format_validate_json(report: &AuditReport, problems: &[String]) -> String {
+    format!(
+        "{{\"valid\":{},\"problems\":{},\"report\":{}}}",
+        problems.is_empty(),
+        json_array(problems, |problem| json_string(problem)),
+        format_audit_json(report)
+    )
+}
+
+// An array with one object per chunk, the data base64 encoded so that binary
+// data survives.
+pub fn format_chunks_json(png: &Png) -> String {
+    json_array(png.chunks(), |chunk| {
+        format!(
+            "{{\"type\":{},\"length\":{},\"crc\":{},\"data_base64\":{}}}",
+            json_string(&chunk.chunk_type().to_string()),
+            chunk.length(),
+            chunk.crc(),
+            json_string(&to_base64(chunk.data()))
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_png() -> Png {
+        let chunks = ["IHDR", "IDAT", "IDAT", "RuSt", "IEND"]
+            .iter()
+            .map(|chunk_type| Chunk::new(ChunkType::from_str(chunk_type).unwrap(), Vec::new()))
+            .collect();
+
+        Png::from_chunks(chunks)
+    }
+
+    #[test]
+    fn test_to_base64() {
+        assert_eq!(to_base64(b""), "");
+        assert_eq!(to_base64(b"f"), "Zg==");
+        assert_eq!(to_base64(b"fo"), "Zm8=");
+        assert_eq!(to_base64(b"foo"), "Zm9v");
+        assert_eq!(to_base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(to_base64(&[0xFB, 0xFF]), "+/8=");
+    }
+
+    #[test]
+    fn test_to_hex() {
+        assert_eq!(to_hex(&[0x00, 0x7F, 0xAB, 0xFF]), "007fabff");
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        let lines = hex_dump(b"Hello, world!\nThis is pngme.\x00");
+        assert_eq!(
+            lines,
+            [
+                "00000000: 4865 6c6c 6f2c 2077 6f72 6c64 210a 5468  Hello, world!.Th",
+                "00000010: 6973 2069 7320 706e 676d 652e 00         is is pngme..",
+            ]
+        );
+        assert!(hex_dump(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_wrap_base64() {
+        let encoded = to_base64(&[0; 90]);
+        assert_eq!(encoded.len(), 120);
+
+        let wrapped = wrap(&encoded, BASE64_WRAP_WIDTH);
+        let lines: Vec<&str> = wrapped.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 76);
+        assert_eq!(lines[1].len(), 44);
+
+        let wrapped = wrap(&encoded, 50);
+        let line_lengths: Vec<usize> = wrapped.lines().map(str::len).collect();
+        assert_eq!(line_lengths, vec![50, 50, 20]);
+    }
+
+    #[test]
+    fn test_wrap_zero_width() {
+        let encoded = to_base64(&[0; 90]);
+        assert_eq!(wrap(&encoded, 0), encoded);
+    }
+
+    #[test]
+    fn test_format_rows_aligned() {
+        let rows: Vec<Vec<String>> = [
+            ["IHDR", "13", "a8d52b4c"],
+            ["IDAT", "65445", "0e1b0a4f"],
+            ["tEXt", "7", "77c3b2aa"],
+        ]
+        .iter()
+        .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+        .collect();
+
+        let lines = format_rows(&rows, None);
+
+        assert_eq!(
+            lines,
+            vec![
+                "IHDR    13 a8d52b4c",
+                "IDAT 65445 0e1b0a4f",
+                "tEXt     7 77c3b2aa",
+            ]
+        );
+    }
+
+    fn testing_report() -> AuditReport {
+        AuditReport {
+            signature_ok: true,
+            chunk_count: 5,
+            bad_crc_chunks: vec![1, 3],
+            ordering_issues: vec!["Last chunk should be IEND but was \"RuSt\".".to_string()],
+            unknown_types: vec!["RuSt".to_string()],
+            trailing_bytes_len: 0,
+        }
+    }
+
+    #[test]
+    fn test_format_audit_json() {
+        let json = format_audit_json(&testing_report());
+
+        assert!(json.contains("\"chunk_count\":5"));
+        assert!(json.contains("\"bad_crc_chunks\":[1,3]"));
+        assert_eq!(
+            json,
+            "{\"signature_ok\":true,\"chunk_count\":5,\"bad_crc_chunks\":[1,3],\
+             \"ordering_issues\":[\"Last chunk should be IEND but was \\\"RuSt\\\".\"],\
+             \"unknown_types\":[\"RuSt\"],\"trailing_bytes_len\":0}"
+        );
+    }
+
+    #[test]
+    fn test_format_validate_json() {
+        let problems = vec!["Chunk 1 failed its checksum.".to_string()];
+        let json = format_validate_json(&testing_report(), &problems);
+
+        assert!(json.starts_with(
+            "{\"valid\":false,\"problems\":[\"Chunk 1 failed its checksum.\"],\"report\":"
+        ));
+        assert!(json.contains("\"chunk_count\":5"));
+        assert!(json.contains("\"bad_crc_chunks\":[1,3]"));
+        assert!(json.ends_with(&format!("{}}}", format_audit_json(&testing_report()))));
+
+        let json = format_validate_json(&AuditReport::default(), &[]);
+        assert!(json.starts_with("{\"valid\":true,\"problems\":[],"));
+    }
+
+    #[test]
+    fn test_format_chunks_json() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), vec![0xFB, 0xFF]);
+        let crc = chunk.crc();
+        let json = format_chunks_json(&Png::from_chunks(vec![chunk]));
+
+        assert_eq!(
+            json,
+            format!(
+                "[{{\"type\":\"ruSt\",\"length\":2,\"crc\":{},\"data_base64\":\"+/8=\"}}]",
+                crc
+            )
+        );
+
+        assert_eq!(format_chunks_json(&Png::new()), "[]");
+        assert_eq!(
+            format_chunks_json(&testing_png())
+                .matches("\"type\"")
+                .count(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_format_audit_text() {
+        let lines = format_audit_text(&testing_report());
+
+        assert_eq!(
+            lines,
+            vec![
+                "Signature: ok",
+                "Chunks: 5",
+                "Bad CRCs: 1, 3",
+                "Ordering issues: 1",
+                "\tLast chunk should be IEND but was \"RuSt\".",
+                "Unknown types: RuSt",
+                "Trailing bytes: 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_counts() {
+        let lines = format_counts(&testing_png(), None);
+
+        assert_eq!(
+            lines,
+            vec![
+                "IDAT 2",
+                "IEND 1",
+                "IHDR 1",
+                "RuSt 1 (non-standard)",
+                "4 distinct chunk types, 1 non-standard",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_counts_tab_separated() {
+        let lines = format_counts(&testing_png(), Some("\t"));
+
+        assert_eq!(lines.len(), 4);
+        for line in &lines {
+            assert_eq!(line.split('\t').count(), 3);
+        }
+        assert_eq!(lines[3], "RuSt\t1\t(non-standard)");
+    }
+
+    #[test]
+    fn test_format_duplicates() {
+        let duplicates = [
+            DuplicateType {
+                chunk_type: "IDAT".to_string(),
+                count: 12,
+                violation: false,
+            },
+            DuplicateType {
+                chunk_type: "IHDR".to_string(),
+                count: 2,
+                violation: true,
+            },
+        ];
+
+        assert_eq!(
+            format_duplicates(&duplicates),
+            vec!["IDAT 12", "IHDR  2 (must be unique)"]
+        );
+    }
+
+    #[test]
+    fn test_format_chunk_list() {
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new(
+            ChunkType::from_str("ruSt").unwrap(),
+            vec![0xFF; 1200],
+        ));
+
+        let lines = format_chunk_list(&png, None);
+
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "0  8 IHDR    0 a8a1ae0a");
+        assert_eq!(lines[3], "3 44 RuSt    0 d484093c");
+        assert!(lines[5].starts_with("5 68 ruSt 1200 "));
+    }
+
+    #[test]
+    fn test_format_chunk_list_separator() {
+        let lines = format_chunk_list(&testing_png(), Some(","));
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "0,8,IHDR,0,a8a1ae0a");
+        assert_eq!(lines[3], "3,44,RuSt,0,d484093c");
+    }
+
+    #[test]
+    fn test_format_profile() {
+        let png = testing_png();
+        let timings: Vec<Duration> = [5, 40, 10, 20, 1]
+            .iter()
+            .map(|micros| Duration::from_micros(*micros))
+            .collect();
+
+        let lines = format_profile(&png, &timings, 3);
+
+        assert_eq!(
+            lines,
+            vec![
+                "chunk 1 IDAT 0 bytes 40µs",
+                "chunk 3 RuSt 0 bytes 20µs",
+                "chunk 2 IDAT 0 bytes 10µs",
+            ]
+        );
+    }
+}
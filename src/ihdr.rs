@@ -0,0 +1,145 @@
+use core::fmt;
+use thiserror::Error;
+
+pub const IHDR_NUM_BYTES: usize = 13;
+
+#[derive(Error, Debug)]
+pub enum IhdrError {
+    #[error("IHDR data should be {} bytes but was {0}.", IHDR_NUM_BYTES)]
+    InvalidLength(usize),
+    #[error("Unknown interlace method {0}.")]
+    UnknownInterlace(u8),
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Interlace {
+    None,
+    Adam7,
+}
+
+impl TryFrom<u8> for Interlace {
+    type Error = IhdrError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Interlace::None),
+            1 => Ok(Interlace::Adam7),
+            _ => Err(IhdrError::UnknownInterlace(value)),
+        }
+    }
+}
+
+impl fmt::Display for Interlace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Interlace::None => write!(f, "none"),
+            Interlace::Adam7 => write!(f, "Adam7"),
+        }
+    }
+}
+
+// The image header, which the PNG spec requires as the first chunk.
+#[derive(Debug)]
+pub struct Ihdr {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlace: Interlace,
+}
+
+impl Ihdr {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn bit_depth(&self) -> u8 {
+        self.bit_depth
+    }
+
+    pub fn color_type(&self) -> u8 {
+        self.color_type
+    }
+
+    pub fn interlace(&self) -> Interlace {
+        self.interlace
+    }
+}
+
+impl TryFrom<&[u8]> for Ihdr {
+    type Error = IhdrError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let bytes: [u8; IHDR_NUM_BYTES] = value
+            .try_into()
+            .map_err(|_| IhdrError::InvalidLength(value.len()))?;
+
+        // Compression and filter methods (bytes 10 and 11) only have a single
+        // defined value, so aren't kept.
+        Ok(Self {
+            width: u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            height: u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+            bit_depth: bytes[8],
+            color_type: bytes[9],
+            interlace: Interlace::try_from(bytes[12])?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ihdr_bytes(interlace: u8) -> Vec<u8> {
+        #[rustfmt::skip]
+        let bytes = vec![
+            0, 0, 0, 50,    // width
+            0, 0, 0, 40,    // height
+            8,              // bit depth
+            6,              // color type
+            0,              // compression method
+            0,              // filter method
+            interlace,      // interlace method
+        ];
+        bytes
+    }
+
+    #[test]
+    fn test_ihdr_from_bytes() {
+        let ihdr = Ihdr::try_from(ihdr_bytes(0).as_ref()).unwrap();
+        assert_eq!(ihdr.width(), 50);
+        assert_eq!(ihdr.height(), 40);
+        assert_eq!(ihdr.bit_depth(), 8);
+        assert_eq!(ihdr.color_type(), 6);
+    }
+
+    #[test]
+    fn test_ihdr_not_interlaced() {
+        let ihdr = Ihdr::try_from(ihdr_bytes(0).as_ref()).unwrap();
+        assert_eq!(ihdr.interlace(), Interlace::None);
+        assert_eq!(ihdr.interlace().to_string(), "none");
+    }
+
+    #[test]
+    fn test_ihdr_interlaced() {
+        let ihdr = Ihdr::try_from(ihdr_bytes(1).as_ref()).unwrap();
+        assert_eq!(ihdr.interlace(), Interlace::Adam7);
+        assert_eq!(ihdr.interlace().to_string(), "Adam7");
+    }
+
+    #[test]
+    fn test_ihdr_unknown_interlace() {
+        let ihdr = Ihdr::try_from(ihdr_bytes(2).as_ref());
+        assert!(matches!(ihdr, Err(IhdrError::UnknownInterlace(2))));
+    }
+
+    #[test]
+    fn test_ihdr_invalid_length() {
+        let ihdr = Ihdr::try_from(&ihdr_bytes(0)[..12]);
+        assert!(matches!(ihdr, Err(IhdrError::InvalidLength(12))));
+    }
+}
@@ -1,133 +1,1053 @@
-use std::{fs, str::FromStr};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    io::{self, BufReader, IsTerminal, Read, Write},
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use clap::Parser;
 
 mod args;
+mod audit;
+mod batch;
 mod chunk;
 mod chunk_type;
+mod codec;
 mod commands;
+mod crypto;
+mod format;
+mod ihdr;
+mod output;
 mod png;
+mod preview;
+mod remote;
+mod text;
 
 use crate::{
+    batch::{collect_png_paths, expand_globs, expand_path, merge_counts, run_batch, WalkOptions},
     chunk::Chunk,
-    chunk_type::ChunkType,
-    commands::{Args, Commands},
-    png::Png,
+    chunk_type::{parse_chunk_type_list, ChunkType},
+    codec::{deflate_with_level, inflate, pack_messages, unpack_messages},
+    commands::{Args, Commands, DataFormat, ReadOptions, ReportFormat},
+    crypto::{decrypt, encrypt},
+    format::{
+        format_audit_json, format_audit_text, format_chunk_list, format_chunks_json, format_counts,
+        format_detected, format_duplicates, format_frequency_table, format_profile,
+        format_validate_json, hex_dump, to_base64, to_hex, wrap, BASE64_WRAP_WIDTH,
+    },
+    ihdr::Ihdr,
+    output::{
+        extract_chunks, is_stdout_path, mirrored_path, output_path_from_template, write_in_place,
+        write_png, write_raw, Console, Destination, WriteOptions,
+    },
+    png::{find_png_start, Png},
+    preview::{decode_luma, render_ascii},
+    remote::{is_url, open_url},
+    text::{latin1_to_string, TextEntry},
 };
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+// Opens the file, or fetches it if the path is an http(s) URL.
+fn open_input(path: &Path) -> Result<Box<dyn Read>, String> {
+    let reader: Result<Box<dyn Read>, String> = if is_url(path) {
+        open_url(path.to_str().unwrap_or_default()).map_err(|err| err.to_string())
+    } else {
+        fs::File::open(path)
+            .map(|file| Box::new(BufReader::new(file)) as Box<dyn Read>)
+            .map_err(|err| err.to_string())
+    };
+
+    reader.map_err(|err| {
+        format!(
+            "Error reading PNG file at {}: {}",
+            path.to_str().unwrap_or("<Invalid Path>"),
+            err
+        )
+    })
+}
+
+// Reads the file, leaving the bytes ready to parse as a PNG.
+fn read_png_bytes(path: &Path, options: &ReadOptions) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    open_input(path)?.read_to_end(&mut data).map_err(|err| {
+        format!(
+            "Error reading PNG file at {}: {}",
+            path.to_str().unwrap_or("<Invalid Path>"),
+            err
+        )
+    })?;
+
+    if options.no_signature {
+        data.splice(0..0, Png::STANDARD_HEADER);
+    }
+
+    let start = if options.scan_offset {
+        find_png_start(&data).ok_or(format!(
+            "No PNG signature found in file at {}",
+            path.to_str().unwrap_or("<Invalid Path>")
+        ))?
+    } else {
+        0
+    };
+
+    data.drain(..start);
+    Ok(data)
+}
+
+// Scanning for the signature, profiling and fixing CRCs all need the whole
+// file up front, but otherwise it's parsed as it's read.
+fn reads_whole_file(options: &ReadOptions) -> bool {
+    options.scan_offset || options.profile.is_some() || options.fix_crc
+}
+
+fn read_png(path: &Path, options: &ReadOptions) -> Result<Png, String> {
+    if reads_whole_file(options) {
+        return parse_png(path, &read_png_bytes(path, options)?, options);
+    }
+
+    let reader = open_png(path, options)?;
+    Png::from_reader(reader).map_err(|err| {
+        format!(
+            "Error parsing PNG data for file at {}: {}",
+            path.to_str().unwrap_or("<Invalid Path>"),
+            err
+        )
+    })
+}
+
+fn open_png(path: &Path, options: &ReadOptions) -> Result<Box<dyn Read>, String> {
+    let reader = open_input(path)?;
+
+    if options.no_signature {
+        Ok(Box::new((&Png::STANDARD_HEADER[..]).chain(reader)))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+// Stops reading at the first chunk of the type, rather than parsing the rest
+// of the file for nothing.
+fn find_chunk(
+    path: &Path,
+    chunk_type: &str,
+    options: &ReadOptions,
+) -> Result<Option<Chunk>, String> {
+    if reads_whole_file(options) {
+        let png = read_png(path, options)?;
+        return Ok(png.chunk_by_type(chunk_type).cloned());
+    }
+
+    let mut found = None;
+    Png::for_each_chunk(open_png(path, options)?, |chunk| {
+        if chunk.type_eq_str(chunk_type) {
+            found = Some(chunk);
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })
+    .map_err(|err| {
+        format!(
+            "Error parsing PNG data for file at {}: {}",
+            path.to_str().unwrap_or("<Invalid Path>"),
+            err
+        )
+    })?;
+
+    Ok(found)
+}
+
+fn parse_png(path: &Path, data: &[u8], options: &ReadOptions) -> Result<Png, String> {
+    if options.fix_crc {
+        return parse_png_lenient(path, data).map(|(png, _)| png);
+    }
+
+    let (png, timings) = Png::try_from_profiled(data).map_err(|err| {
+        format!(
+            "Error parsing PNG data for file at {}: {}",
+            path.to_str().unwrap_or("<Invalid Path>"),
+            err
+        )
+    })?;
+
+    if let Some(count) = options.profile {
+        for line in format_profile(&png, &timings, count) {
+            eprintln!("{}", line);
+        }
+    }
+
+    Ok(png)
+}
+
+fn parse_png_lenient(path: &Path, data: &[u8]) -> Result<(Png, Vec<usize>), String> {
+    Png::try_from_lenient(data).map_err(|err| {
+        format!(
+            "Error parsing PNG data for file at {}: {}",
+            path.to_str().unwrap_or("<Invalid Path>"),
+            err
+        )
+    })
+}
+
+// Reads the message as raw bytes, so binary data survives unchanged.
+fn read_stdin_message() -> Result<Vec<u8>, String> {
+    let mut stdin = io::stdin();
+    if stdin.is_terminal() {
+        return Err("No message given, either as an argument or piped to stdin".to_string());
+    }
+
+    let mut message = Vec::new();
+    stdin
+        .read_to_end(&mut message)
+        .map_err(|err| format!("Error reading message from stdin: {}", err))?;
+    Ok(message)
+}
+
+fn decode_text(bytes: &[u8], lossy: bool) -> String {
+    if lossy {
+        String::from_utf8_lossy(bytes).into_owned()
+    } else {
+        String::from_utf8(bytes.to_vec()).unwrap_or("<Not Representable>".to_string())
+    }
+}
+
+// Reads the PNG at `path`, adds the chunk, placing it straight after the last
+// chunk of type `after` if given, and writes the result out.
+fn encode_one(
+    path: &Path,
+    chunk: Chunk,
+    after: Option<&str>,
+    validate_input: bool,
+    read_options: &ReadOptions,
+    destination: &Destination,
+    write_options: WriteOptions,
+) -> Result<(), String> {
+    let mut png = read_png(path, read_options)?;
+
+    if validate_input {
+        png.validate().map_err(|errors| {
+            format!(
+                "Refusing to modify malformed PNG file at {}:\n{}",
+                path.to_str().unwrap_or("<Invalid Path>"),
+                errors
+                    .iter()
+                    .map(|err| err.to_string())
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            )
+        })?;
+    }
+
+    match after {
+        Some(target) => png.insert_chunk_after_type(target, chunk).map_err(|err| {
+            format!(
+                "Can't place chunk in PNG file at {}: {}",
+                path.to_str().unwrap_or("<Invalid Path>"),
+                err
+            )
+        })?,
+        None => png.append_chunk(chunk),
+    }
+
+    write_png(
+        destination,
+        &png.as_bytes(),
+        write_options,
+        &mut io::stdout().lock(),
+    )
+    .map_err(|err| err.to_string())
+}
+
+// URLs are left alone, as a query string can look like a glob.
+fn expand_input(path: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if is_url(path) {
+        Ok(vec![path.to_path_buf()])
+    } else {
+        Ok(expand_path(path)?)
+    }
+}
+
+// Where `file` belongs in the mirrored tree under `out_dir`, creating the
+// directories it needs on the way.
+fn prepare_mirrored(root: &Path, file: &Path, out_dir: &Path) -> Result<PathBuf, String> {
+    let mirrored = mirrored_path(root, file, out_dir);
+    if let Some(parent) = mirrored.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            format!(
+                "Error creating output directory {}: {}",
+                parent.to_str().unwrap_or("<Invalid Path>"),
+                err
+            )
+        })?;
+    }
+    Ok(mirrored)
+}
+
+// Runs `f` for each of the paths a glob expanded to. A single path fails just
+// as it would have unexpanded, while several carry on past failures, as
+// batches do.
+fn for_each_path<F>(paths: &[PathBuf], mut f: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&Path) -> Result<(), Box<dyn Error>>,
+{
+    if let [path] = paths {
+        return f(path);
+    }
+
+    let report = run_batch(paths, |path| f(path).map_err(|err| err.to_string()));
+    for line in report.summary() {
+        eprintln!("{}", line);
+    }
+    Ok(report.into_result()?)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
+    let status_writer: Box<dyn Write> = if args.cmd.writes_png_to_stdout() {
+        Box::new(io::stderr())
+    } else {
+        Box::new(io::stdout())
+    };
+    let mut console = Console::new(status_writer, args.quiet);
     match args.cmd {
         Commands::Encode {
             path,
             chunk_type,
             message,
             output,
+            output_template,
+            multi,
+            assume_yes,
+            validate_input,
+            into,
+            append: _,
+            after,
+            compress,
+            compress_level,
+            stdout,
+            preserve_mtime,
+            encrypt: passphrase,
+            recursive,
+            text_keyword,
+            data_file,
+            out_dir,
         } => {
-            let data: Vec<u8> = fs::read(path.clone()).map_err(|err| {
+            let chunk_type_value = ChunkType::from_str(&chunk_type).map_err(|err| {
                 format!(
-                    "Error reading PNG file at {}: {}",
-                    path.to_str().unwrap_or("<Invalid Path>"),
-                    err
+                    "Invalid chunk type {:?}: {}",
+                    chunk_type,
+                    err.guidance(&chunk_type)
                 )
             })?;
 
-            let mut png = Png::try_from(data.as_slice()).map_err(|err| {
-                format!(
-                    "Error parsing PNG data for file at {}: {}",
-                    path.to_str().unwrap_or("<Invalid Path>"),
-                    err
-                )
-            })?;
+            // Only a message given as an argument is text; bytes from a file or
+            // piped in are kept exactly as they are.
+            let message_is_text = message.is_some();
+            // A message given as an argument or file wins over anything piped
+            // in.
+            let message = match (message, data_file) {
+                (Some(message), _) => message.into_bytes(),
+                (None, Some(data_file)) => fs::read(&data_file).map_err(|err| {
+                    format!(
+                        "Error reading data file at {}: {}",
+                        data_file.to_str().unwrap_or("<Invalid Path>"),
+                        err
+                    )
+                })?,
+                (None, None) => read_stdin_message()?,
+            };
 
-            let chunk_type_value = ChunkType::from_str(&chunk_type)
-                .map_err(|err| format!("Invalid chunk type {:?}: {}", chunk_type, err))?;
+            let data = if let Some(keyword) = text_keyword {
+                if chunk_type_value != ChunkType::TEXT {
+                    return Err("--text-keyword can only be used with tEXt chunks".into());
+                }
+                // tEXt is Latin-1, in which every byte is a character.
+                let value = if message_is_text {
+                    String::from_utf8(message)?
+                } else {
+                    latin1_to_string(&message)
+                };
+                TextEntry::new_checked(&keyword, &value)
+                    .map_err(|err| format!("Invalid tEXt entry: {}", err))?
+                    .as_bytes()
+            } else if multi.is_empty() {
+                message
+            } else {
+                let extra = multi.into_iter().map(String::into_bytes);
+                pack_messages(&[message].into_iter().chain(extra).collect::<Vec<_>>())
+            };
 
-            let chunk = Chunk::new(chunk_type_value, message.into_bytes());
+            let data = if compress {
+                deflate_with_level(&data, compress_level)
+            } else {
+                data
+            };
+            // Compression has to come first, as encrypted data won't compress.
+            let data = match passphrase {
+                Some(passphrase) => encrypt(&data, &passphrase),
+                None => data,
+            };
+            let chunk = Chunk::new_checked(chunk_type_value, data)
+                .map_err(|err| format!("Can't encode chunk: {}", err))?;
 
-            png.append_chunk(chunk);
+            let after = after.or(into);
+            let write_options = WriteOptions {
+                assume_yes,
+                preserve_mtime,
+            };
 
-            let output_path = output.unwrap_or(path);
+            if recursive {
+                if output.as_deref().is_some_and(is_stdout_path) {
+                    return Err("Can't write several PNG files to stdout".into());
+                }
 
-            fs::write(output_path, png.as_bytes())?;
+                // OUTPUT names a directory here, so means the same as --out-dir.
+                let out_dir = out_dir.or(output);
+                let paths = collect_png_paths(std::slice::from_ref(&path), WalkOptions::default())?;
+                let report = run_batch(&paths, |file| {
+                    let destination = match (&out_dir, &output_template) {
+                        (Some(dir), _) => Destination::File(prepare_mirrored(&path, file, dir)?),
+                        (None, Some(template)) => {
+                            Destination::File(output_path_from_template(file, template))
+                        }
+                        (None, None) => Destination::InPlace(file.to_path_buf()),
+                    };
+                    encode_one(
+                        file,
+                        chunk.clone(),
+                        after.as_deref(),
+                        validate_input,
+                        &args.read,
+                        &destination,
+                        write_options,
+                    )?;
+                    if let Some(written) = destination.path() {
+                        console
+                            .status(&format!("Wrote {}", written.display()))
+                            .map_err(|err| err.to_string())?;
+                    }
+                    Ok(())
+                });
+
+                for line in report.summary() {
+                    eprintln!("{}", line);
+                }
+                console.status(&format!(
+                    "Encoded {} of {} files",
+                    report.succeeded(),
+                    paths.len()
+                ))?;
+                report.into_result()?;
+                return Ok(());
+            }
+
+            let output = match out_dir {
+                Some(dir) => Some(prepare_mirrored(&path, &path, &dir)?),
+                None => output.or_else(|| {
+                    output_template
+                        .as_deref()
+                        .map(|template| output_path_from_template(&path, template))
+                }),
+            };
+            let destination = match output {
+                Some(output_path) if is_stdout_path(&output_path) => Destination::Stdout,
+                Some(output_path) => Destination::File(output_path),
+                None if stdout => Destination::Stdout,
+                None => Destination::InPlace(path.clone()),
+            };
+            encode_one(
+                &path,
+                chunk,
+                after.as_deref(),
+                validate_input,
+                &args.read,
+                &destination,
+                write_options,
+            )?;
+            if let Some(written) = destination.path() {
+                console.status(&format!("Wrote {}", written.display()))?;
+            }
         }
 
-        Commands::Decode { path, chunk_type } => {
-            let data: Vec<u8> = fs::read(path.clone()).map_err(|err| {
-                format!(
-                    "Error reading PNG file at {}: {}",
-                    path.to_str().unwrap_or("<Invalid Path>"),
-                    err
-                )
-            })?;
+        Commands::Decode {
+            path,
+            chunk_type,
+            lossy,
+            format,
+            wrap: wrap_width,
+            raw,
+            multi,
+            all,
+            decompress,
+            decrypt: passphrase,
+        } => {
+            let paths = expand_input(&path)?;
+            let several = paths.len() > 1;
+            if raw && several {
+                return Err("Can't write raw data from several files at once".into());
+            }
 
-            let png = Png::try_from(data.as_slice()).map_err(|err| {
-                format!(
-                    "Error parsing PNG data for file at {}: {}",
-                    path.to_str().unwrap_or("<Invalid Path>"),
-                    err
-                )
+            for_each_path(&paths, |path| {
+                // Output from several files is told apart by file name.
+                let prefix = if several {
+                    format!("{}: ", path.display())
+                } else {
+                    String::new()
+                };
+
+                let chunks: Vec<Chunk> = if all {
+                    let png = read_png(path, &args.read)?;
+                    png.chunks_by_type(&chunk_type)
+                        .into_iter()
+                        .cloned()
+                        .collect()
+                } else {
+                    find_chunk(path, &chunk_type, &args.read)?
+                        .into_iter()
+                        .collect()
+                };
+                if chunks.is_empty() {
+                    return Err(format!("Chunk type {:?} not found", chunk_type).into());
+                }
+
+                for chunk in chunks {
+                    let data = match &passphrase {
+                        Some(passphrase) => decrypt(chunk.data(), passphrase).map_err(|err| {
+                            format!("Can't decrypt chunk type {:?}: {}", chunk_type, err)
+                        })?,
+                        None => chunk.data().to_vec(),
+                    };
+                    let data = if decompress {
+                        inflate(&data).map_err(|err| {
+                            format!(
+                                "Chunk type {:?} doesn't hold compressed data: {}",
+                                chunk_type, err
+                            )
+                        })?
+                    } else {
+                        data
+                    };
+
+                    if raw {
+                        write_raw(&mut io::stdout().lock(), &data)?;
+                        continue;
+                    }
+
+                    if multi {
+                        let messages = unpack_messages(&data).map_err(|err| {
+                            format!(
+                                "Chunk type {:?} doesn't hold packed messages: {}",
+                                chunk_type, err
+                            )
+                        })?;
+                        for (idx, message) in messages.iter().enumerate() {
+                            let label = format!("Message {}", idx + 1);
+                            console.labelled(
+                                &format!("{}{}", prefix, label),
+                                &decode_text(message, lossy),
+                            )?;
+                        }
+                        continue;
+                    }
+
+                    match format {
+                        // Print a tEXt chunk's keyword/value pair apart, as other
+                        // PNG tools would.
+                        DataFormat::Text if *chunk.chunk_type() == ChunkType::TEXT => {
+                            match TextEntry::try_from(data.as_slice()) {
+                                Ok(entry) => {
+                                    console
+                                        .labelled(&format!("{}Keyword", prefix), entry.keyword())?;
+                                    console.labelled(&format!("{}Value", prefix), entry.value())?;
+                                }
+                                Err(_) => console.labelled(
+                                    &format!("{}Decoded", prefix),
+                                    &latin1_to_string(&data),
+                                )?,
+                            }
+                        }
+                        DataFormat::Text => {
+                            console.labelled(
+                                &format!("{}Decoded", prefix),
+                                &decode_text(&data, lossy),
+                            )?;
+                        }
+                        DataFormat::Hex => {
+                            for line in hex_dump(&data) {
+                                println!("{}{}", prefix, line);
+                            }
+                        }
+                        DataFormat::HexFlat => {
+                            println!(
+                                "{}{}",
+                                prefix,
+                                wrap(&to_hex(&data), wrap_width.unwrap_or(0))
+                            );
+                        }
+                        DataFormat::Base64 => {
+                            println!(
+                                "{}{}",
+                                prefix,
+                                wrap(&to_base64(&data), wrap_width.unwrap_or(BASE64_WRAP_WIDTH))
+                            );
+                        }
+                    }
+                }
+                Ok(())
             })?;
+        }
+
+        Commands::Remove {
+            path,
+            chunk_type,
+            types_file,
+            index,
+            all,
+            assume_yes,
+            stdout,
+            preserve_mtime,
+        } => {
+            let mut png = read_png(&path, &args.read)?;
 
-            let chunk = png
-                .chunk_by_type(&chunk_type)
-                .ok_or(format!("Chunk type {:?} not found", chunk_type))?;
+            if let Some(types_file) = types_file {
+                let list = fs::read_to_string(&types_file).map_err(|err| {
+                    format!(
+                        "Error reading chunk types file at {}: {}",
+                        types_file.to_str().unwrap_or("<Invalid Path>"),
+                        err
+                    )
+                })?;
 
-            println!(
-                "Decoded: {}",
-                chunk
-                    .data_as_string()
-                    .unwrap_or("<Not Representable>".to_string())
-            );
+                let chunk_types = parse_chunk_type_list(&list)
+                    .map_err(|err| format!("Invalid chunk type in types file: {}", err))?;
+
+                let removed = png.remove_chunks_of_types(&chunk_types);
+                console.status(&format!("Removed {} chunks", removed))?;
+            } else if let Some(index) = index {
+                let removed = png
+                    .remove_chunk_by_index(index)
+                    .map_err(|err| format!("Could not remove chunk: {}", err))?;
+                console.status(&format!(
+                    "Removed {} chunk at index {}",
+                    removed.chunk_type(),
+                    index
+                ))?;
+            } else if let Some(chunk_type) = chunk_type {
+                if all {
+                    let removed = png.remove_all_chunks(&chunk_type);
+                    console.status(&format!("Removed {} chunks", removed))?;
+                    if removed == 0 && !stdout {
+                        return Ok(());
+                    }
+                } else {
+                    png.remove_first_chunk(&chunk_type).map_err(|err| {
+                        format!("Could not remove chunk type {:?}: {}", chunk_type, err)
+                    })?;
+                }
+            }
+
+            let destination = if stdout {
+                Destination::Stdout
+            } else {
+                Destination::InPlace(path)
+            };
+            let write_options = WriteOptions {
+                assume_yes,
+                preserve_mtime,
+            };
+            write_png(
+                &destination,
+                &png.as_bytes(),
+                write_options,
+                &mut io::stdout().lock(),
+            )?;
         }
 
-        Commands::Remove { path, chunk_type } => {
-            let data: Vec<u8> = fs::read(path.clone()).map_err(|err| {
-                format!(
-                    "Error reading PNG file at {}: {}",
+        Commands::Copy {
+            source,
+            dest,
+            chunk_type,
+            output,
+            assume_yes,
+        } => {
+            // Copied as the chunk itself, so binary data arrives untouched.
+            let chunk = find_chunk(&source, &chunk_type, &args.read)?.ok_or(format!(
+                "Chunk type {:?} not found in file at {}",
+                chunk_type,
+                source.to_str().unwrap_or("<Invalid Path>")
+            ))?;
+
+            let destination = match output {
+                Some(output_path) if is_stdout_path(&output_path) => Destination::Stdout,
+                Some(output_path) => Destination::File(output_path),
+                None => Destination::InPlace(dest.clone()),
+            };
+            let write_options = WriteOptions {
+                assume_yes,
+                ..Default::default()
+            };
+            encode_one(
+                &dest,
+                chunk,
+                None,
+                false,
+                &args.read,
+                &destination,
+                write_options,
+            )?;
+            if let Some(written) = destination.path() {
+                console.status(&format!("Wrote {}", written.display()))?;
+            }
+        }
+
+        Commands::Clean {
+            path,
+            output,
+            dedup_text,
+            trailing,
+            assume_yes,
+        } => {
+            let mut png = read_png(&path, &args.read)?;
+
+            if dedup_text {
+                let removed = png.deduplicate_text_by_keyword();
+                console.status(&format!("Removed {} duplicate text chunks", removed))?;
+            }
+
+            if trailing {
+                let (chunks, bytes) = png.strip_after_iend();
+                console.status(&format!(
+                    "Removed {} chunks and {} trailing bytes after IEND",
+                    chunks, bytes
+                ))?;
+            }
+
+            match output {
+                Some(output_path) => fs::write(output_path, png.as_bytes())?,
+                None => write_in_place(&path, &png.as_bytes(), assume_yes)?,
+            }
+        }
+
+        Commands::Repair {
+            path,
+            output,
+            assume_yes,
+        } => {
+            let (png, bad_crcs) = parse_png_lenient(&path, &read_png_bytes(&path, &args.read)?)?;
+
+            for index in &bad_crcs {
+                console.status(&format!(
+                    "Chunk {} ({}) had a bad CRC",
+                    index,
+                    png.chunks()[*index].chunk_type()
+                ))?;
+            }
+            console.status(&format!("Corrected {} CRCs", bad_crcs.len()))?;
+
+            // Nothing to fix means nothing worth overwriting.
+            match output {
+                Some(output_path) => fs::write(output_path, png.as_bytes())?,
+                None if bad_crcs.is_empty() => {}
+                None => write_in_place(&path, &png.as_bytes(), assume_yes)?,
+            }
+        }
+
+        Commands::Count {
+            path,
+            field_separator,
+        } => {
+            let png = read_png(&path, &args.read)?;
+
+            for line in format_counts(&png, field_separator.as_deref()) {
+                println!("{}", line);
+            }
+        }
+
+        Commands::ExtractTrailing { path, output } => {
+            let png = read_png(&path, &args.read)?;
+
+            let trailing = png.trailing_bytes();
+            if trailing.is_empty() {
+                console.status("No trailing bytes found after IEND")?;
+            } else {
+                fs::write(&output, trailing)?;
+                console.status(&format!(
+                    "Extracted {} trailing bytes to {}",
+                    trailing.len(),
+                    output.to_str().unwrap_or("<Invalid Path>")
+                ))?;
+            }
+        }
+
+        Commands::Extract {
+            path,
+            output,
+            exclude_standard: _,
+            include_standard,
+        } => {
+            let png = read_png(&path, &args.read)?;
+
+            let written = extract_chunks(&png, &output, include_standard)?;
+            for chunk_path in &written {
+                console.status(&chunk_path.display().to_string())?;
+            }
+            console.status(&format!("Extracted {} chunks", written.len()))?;
+        }
+
+        Commands::Validate {
+            path,
+            checksum_only,
+            two_pass,
+            report_duplicates,
+            format,
+        } => {
+            let bytes = read_png_bytes(&path, &args.read)?;
+            let problems: Vec<String> = if checksum_only {
+                match Png::validate_checksums(&bytes) {
+                    Ok(()) => Vec::new(),
+                    Err(errors) => errors.iter().map(|err| err.to_string()).collect(),
+                }
+            } else {
+                let mut problems: Vec<String> = match Png::validate_bytes(&bytes) {
+                    Ok(()) => Vec::new(),
+                    Err(errors) => errors.iter().map(|err| err.to_string()).collect(),
+                };
+
+                // Only a file which passed the first pass can be parsed for the
+                // rest.
+                if problems.is_empty() && (two_pass || report_duplicates) {
+                    let png = parse_png(&path, &bytes, &args.read)?;
+
+                    if report_duplicates {
+                        let duplicates = png.duplicate_types();
+                        if format != Some(ReportFormat::Json) {
+                            for line in format_duplicates(&duplicates) {
+                                println!("{}", line);
+                            }
+                        }
+                        problems.extend(duplicates.iter().filter(|dup| dup.violation).map(|dup| {
+                            format!(
+                                "Chunk type {} appears {} times but must be unique.",
+                                dup.chunk_type, dup.count
+                            )
+                        }));
+                    }
+
+                    if two_pass {
+                        if let Some(offset) = png.first_difference_from(&bytes) {
+                            problems.push(format!(
+                                "Re-serialized PNG differs from the file from byte {}.",
+                                offset
+                            ));
+                        }
+                    }
+                }
+                problems
+            };
+
+            match format {
+                Some(ReportFormat::Text) => {
+                    for line in format_audit_text(&Png::audit(&bytes)) {
+                        println!("{}", line);
+                    }
+                }
+                Some(ReportFormat::Json) => {
+                    println!("{}", format_validate_json(&Png::audit(&bytes), &problems));
+                }
+                None => {}
+            }
+
+            if !problems.is_empty() {
+                return Err(format!(
+                    "Invalid PNG file at {}:\n{}",
                     path.to_str().unwrap_or("<Invalid Path>"),
-                    err
+                    problems.join("\n")
                 )
-            })?;
+                .into());
+            }
+            // Keep stdout to the one JSON object.
+            if format != Some(ReportFormat::Json) {
+                console.status(&format!("{} is valid", path.display()))?;
+            }
+        }
 
-            let mut png = Png::try_from(data.as_slice()).map_err(|err| {
-                format!(
-                    "Error parsing PNG data for file at {}: {}",
-                    path.to_str().unwrap_or("<Invalid Path>"),
-                    err
+        Commands::Audit { path, format } => {
+            let report = Png::audit(&read_png_bytes(&path, &args.read)?);
+
+            match format {
+                ReportFormat::Text => {
+                    for line in format_audit_text(&report) {
+                        println!("{}", line);
+                    }
+                }
+                ReportFormat::Json => println!("{}", format_audit_json(&report)),
+            }
+
+            if !report.is_clean() {
+                return Err(format!(
+                    "Problems found in PNG file at {}",
+                    path.to_str().unwrap_or("<Invalid Path>")
                 )
+                .into());
+            }
+        }
+
+        Commands::Digest {
+            paths,
+            follow_symlinks,
+            max_depth,
+            only_type,
+            glob,
+            chunk_type_stats,
+        } => {
+            let paths = if glob || cfg!(windows) {
+                expand_globs(&paths)?
+            } else {
+                paths
+            };
+            let walk = WalkOptions {
+                follow_symlinks,
+                max_depth,
+            };
+            let paths = collect_png_paths(&paths, walk)?;
+            let mut type_counts = BTreeMap::new();
+            let report = run_batch(&paths, |path| {
+                let png = read_png(path, &args.read)?;
+                let digest = match &only_type {
+                    Some(chunk_type) => png.digest_of_type(chunk_type),
+                    None => png.digest(),
+                };
+                println!("{}  {}", to_hex(&digest), path.display());
+                if chunk_type_stats {
+                    merge_counts(&mut type_counts, png.chunk_counts());
+                }
+                Ok(())
+            });
+
+            if chunk_type_stats {
+                println!();
+                for line in format_frequency_table(&type_counts) {
+                    println!("{}", line);
+                }
+            }
+
+            for line in report.summary() {
+                eprintln!("{}", line);
+            }
+            report.into_result()?;
+        }
+
+        Commands::Chunks {
+            path,
+            field_separator,
+        } => {
+            let paths = expand_input(&path)?;
+            let several = paths.len() > 1;
+
+            for_each_path(&paths, |path| {
+                let png = read_png(path, &args.read)?;
+
+                if several {
+                    println!("{}:", path.display());
+                }
+                for line in format_chunk_list(&png, field_separator.as_deref()) {
+                    println!("{}", line);
+                }
+                Ok(())
             })?;
+        }
 
-            png.remove_first_chunk(&chunk_type)
-                .map_err(|err| format!("Could not remove chunk type {:?}: {}", chunk_type, err))?;
+        Commands::Detect { path, min_entropy } => {
+            let png = read_png(&path, &args.read)?;
 
-            fs::write(path, png.as_bytes())?;
+            let detected = png.detect_suspicious(min_entropy);
+            if detected.is_empty() {
+                console.status("No suspicious chunks found")?;
+            }
+            for line in format_detected(&detected) {
+                println!("{}", line);
+            }
         }
 
-        Commands::Print { path } => {
-            let data: Vec<u8> = fs::read(path.clone()).map_err(|err| {
+        Commands::Probe { path } => {
+            let ihdr = if args.read.scan_offset {
+                Png::read_ihdr(&read_png_bytes(&path, &args.read)?[..])
+            } else {
+                Png::read_ihdr(open_png(&path, &args.read)?)
+            }
+            .map_err(|err| {
                 format!(
-                    "Error reading PNG file at {}: {}",
+                    "Error reading IHDR for file at {}: {}",
                     path.to_str().unwrap_or("<Invalid Path>"),
                     err
                 )
             })?;
 
-            let png = Png::try_from(data.as_slice()).map_err(|err| {
+            println!("{}x{}", ihdr.width(), ihdr.height());
+        }
+
+        Commands::Preview { path, width } => {
+            let png = read_png(&path, &args.read)?;
+            let header = png
+                .chunk_by_type("IHDR")
+                .ok_or_else(|| "PNG file has no IHDR chunk".to_string())?;
+            let ihdr = Ihdr::try_from(header.data())
+                .map_err(|err| format!("Invalid IHDR chunk: {}", err))?;
+
+            let luma = decode_luma(&ihdr, &png.chunk_data_concat("IDAT")).map_err(|err| {
                 format!(
-                    "Error parsing PNG data for file at {}: {}",
+                    "Error previewing file at {}: {}",
                     path.to_str().unwrap_or("<Invalid Path>"),
                     err
                 )
             })?;
 
-            println!(
-                "{}\n{}",
-                path.file_name()
+            for line in render_ascii(&luma, width) {
+                println!("{}", line);
+            }
+        }
+
+        Commands::Print {
+            path,
+            lossy,
+            raw_chunks,
+            json,
+        } => {
+            for_each_path(&expand_input(&path)?, |path| {
+                let png = read_png(path, &args.read)?;
+
+                if raw_chunks {
+                    write_raw(&mut io::stdout().lock(), &png.chunks_as_bytes())?;
+                    return Ok(());
+                }
+
+                if json {
+                    println!("{}", format_chunks_json(&png));
+                    return Ok(());
+                }
+
+                let file_name = path
+                    .file_name()
                     .and_then(|name| name.to_str())
-                    .unwrap_or("<Unknown Filename>"),
-                png
-            );
+                    .unwrap_or("<Unknown Filename>");
+
+                println!("{}", file_name);
+
+                if let Some(header) = png.chunk_by_type("IHDR") {
+                    let ihdr = Ihdr::try_from(header.data())
+                        .map_err(|err| format!("Invalid IHDR chunk: {}", err))?;
+                    println!("Interlace: {}", ihdr.interlace());
+                }
+
+                if lossy {
+                    println!("{}", png.display_lossy());
+                } else {
+                    println!("{}", png);
+                }
+                Ok(())
+            })?;
         }
     }
 
@@ -0,0 +1,437 @@
+use std::{
+    fs,
+    io::{self, BufRead, IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+use crate::{png::Png, remote::is_url};
+
+#[derive(Error, Debug)]
+pub enum OutputError {
+    #[error("Declined to overwrite {0}.")]
+    Declined(PathBuf),
+    #[error("An output must be given when reading from a URL ({0}).")]
+    UrlInPlace(PathBuf),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+pub fn confirm_overwrite<R: BufRead, W: Write>(
+    path: &Path,
+    input: &mut R,
+    prompt: &mut W,
+) -> io::Result<bool> {
+    write!(prompt, "Overwrite {}? [y/N] ", path.display())?;
+    prompt.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+// Only prompts when someone is around to answer, so piped and scripted use
+// behaves as though `assume_yes` were set.
+pub fn write_in_place(path: &Path, bytes: &[u8], assume_yes: bool) -> Result<(), OutputError> {
+    check_in_place(path)?;
+
+    let stdin = io::stdin();
+    if !assume_yes
+        && stdin.is_terminal()
+        && !confirm_overwrite(path, &mut stdin.lock(), &mut io::stderr())?
+    {
+        return Err(OutputError::Declined(path.to_path_buf()));
+    }
+
+    fs::write(path, bytes)?;
+    Ok(())
+}
+
+// A PNG fetched from a URL has no file to write back to; one named after the
+// URL would be a surprise at best.
+fn check_in_place(path: &Path) -> Result<(), OutputError> {
+    if is_url(path) {
+        return Err(OutputError::UrlInPlace(path.to_path_buf()));
+    }
+    Ok(())
+}
+
+// Where a command writes the PNG it has modified.
+pub enum Destination {
+    Stdout,
+    File(PathBuf),
+    InPlace(PathBuf),
+}
+
+impl Destination {
+    // The file written, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Destination::Stdout => None,
+            Destination::File(path) | Destination::InPlace(path) => Some(path),
+        }
+    }
+}
+
+// An output path of `-` means stdout, as is usual for command line tools.
+pub fn is_stdout_path(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+// Options controlling how a modified PNG is written back over its input.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WriteOptions {
+    // Skip asking for confirmation before overwriting.
+    pub assume_yes: bool,
+    // Keep the file's modification time as it was before the write.
+    pub preserve_mtime: bool,
+}
+
+pub fn write_png<W: Write>(
+    destination: &Destination,
+    bytes: &[u8],
+    options: WriteOptions,
+    stdout: &mut W,
+) -> Result<(), OutputError> {
+    match destination {
+        Destination::Stdout => write_raw(stdout, bytes)?,
+        Destination::File(path) => fs::write(path, bytes)?,
+        Destination::InPlace(path) if options.preserve_mtime => {
+            check_in_place(path)?;
+            with_mtime_preserved(path, || write_in_place(path, bytes, options.assume_yes))?
+        }
+        Destination::InPlace(path) => write_in_place(path, bytes, options.assume_yes)?,
+    }
+    Ok(())
+}
+
+// Restores the file's modification time after `write`, so that tools watching
+// for changes don't notice it.
+pub fn with_mtime_preserved<F>(path: &Path, write: F) -> Result<(), OutputError>
+where
+    F: FnOnce() -> Result<(), OutputError>,
+{
+    let modified = fs::metadata(path)?.modified()?;
+    write()?;
+    fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(modified)?;
+    Ok(())
+}
+
+// Writes bytes exactly as given, for piping binary data into other programs.
+pub fn write_raw<W: Write>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    writer.write_all(bytes)?;
+    writer.flush()
+}
+
+// Fills in `{stem}` and `{ext}` in the template from the input's file name,
+// e.g. "{stem}_tagged.{ext}" turns `images/cat.png` into
+// `images/cat_tagged.png`. The result sits alongside the input.
+pub fn output_path_from_template(input: &Path, template: &str) -> PathBuf {
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = input.extension().unwrap_or_default().to_string_lossy();
+    let name = template.replace("{stem}", &stem).replace("{ext}", &ext);
+
+    input.with_file_name(name)
+}
+
+// Where `file`, found somewhere under `root`, belongs when the directory tree is
+// mirrored into `out_dir`.
+pub fn mirrored_path(root: &Path, file: &Path, out_dir: &Path) -> PathBuf {
+    match file.strip_prefix(root) {
+        Ok(relative) if !relative.as_os_str().is_empty() => out_dir.join(relative),
+        _ => out_dir.join(file.file_name().unwrap_or_default()),
+    }
+}
+
+// Keeps status messages about what a command did apart from the output which
+// is the point of the command, so that `--quiet` only silences the former.
+pub struct Console<W: Write> {
+    writer: W,
+    quiet: bool,
+}
+
+impl<W: Write> Console<W> {
+    pub fn new(writer: W, quiet: bool) -> Console<W> {
+        Self { writer, quiet }
+    }
+
+    pub fn status(&mut self, message: &str) -> io::Result<()> {
+        if self.quiet {
+            return Ok(());
+        }
+        writeln!(self.writer, "{}", message)
+    }
+
+    // The label is chatter, but the value is what was asked for, so only the
+    // label is dropped when quiet.
+    pub fn labelled(&mut self, label: &str, value: &str) -> io::Result<()> {
+        if self.quiet {
+            writeln!(self.writer, "{}", value)
+        } else {
+            writeln!(self.writer, "{}: {}", label, value)
+        }
+    }
+}
+
+// Writes the data of each chunk to its own file in `dir`, named after its index
+// and type, e.g. `3_ruSt.bin`. Chunk types defined by the spec are skipped
+// unless `include_standard` is set. Returns the paths written.
+pub fn extract_chunks(png: &Png, dir: &Path, include_standard: bool) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+
+    let mut written = Vec::new();
+    for (idx, chunk) in png.chunks().iter().enumerate() {
+        if !include_standard && chunk.chunk_type().is_standard() {
+            continue;
+        }
+
+        let path = dir.join(format!("{}_{}.bin", idx, chunk.chunk_type()));
+        fs::write(&path, chunk.data())?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pngme_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_confirm_overwrite_accepts_yes() {
+        let mut prompt = Vec::new();
+        let confirmed =
+            confirm_overwrite(Path::new("image.png"), &mut Cursor::new("y\n"), &mut prompt)
+                .unwrap();
+
+        assert!(confirmed);
+        assert_eq!(
+            String::from_utf8(prompt).unwrap(),
+            "Overwrite image.png? [y/N] "
+        );
+    }
+
+    #[test]
+    fn test_confirm_overwrite_defaults_to_no() {
+        let confirmed = confirm_overwrite(
+            Path::new("image.png"),
+            &mut Cursor::new("\n"),
+            &mut io::sink(),
+        )
+        .unwrap();
+
+        assert!(!confirmed);
+    }
+
+    #[test]
+    fn test_output_path_from_template() {
+        let input = Path::new("images").join("cat.png");
+
+        assert_eq!(
+            output_path_from_template(&input, "{stem}_tagged.{ext}"),
+            Path::new("images").join("cat_tagged.png")
+        );
+        assert_eq!(
+            output_path_from_template(Path::new("cat"), "{stem}.{ext}.bak"),
+            Path::new("cat..bak")
+        );
+    }
+
+    #[test]
+    fn test_mirrored_path() {
+        assert_eq!(
+            mirrored_path(
+                Path::new("images"),
+                Path::new("images/cats/tabby.png"),
+                Path::new("out")
+            ),
+            Path::new("out/cats/tabby.png")
+        );
+        // A single file given as the root lands directly in the output dir.
+        assert_eq!(
+            mirrored_path(
+                Path::new("images/tabby.png"),
+                Path::new("images/tabby.png"),
+                Path::new("out")
+            ),
+            Path::new("out/tabby.png")
+        );
+    }
+
+    #[test]
+    fn test_write_raw() {
+        use crate::{chunk::Chunk, chunk_type::ChunkType};
+        use std::str::FromStr;
+
+        let data = vec![0x1F, 0x8B, 0x00, b'\n', 0xFF];
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), data);
+
+        let mut stdout = Vec::new();
+        write_raw(&mut stdout, chunk.data()).unwrap();
+
+        assert_eq!(stdout, chunk.data());
+    }
+
+    fn console_session(quiet: bool) -> String {
+        let mut stdout = Vec::new();
+        let mut console = Console::new(&mut stdout, quiet);
+
+        // An encode only reports what it did, while a decode prints the message.
+        console.status("Wrote image.png").unwrap();
+        console.labelled("Decoded", "Hidden message").unwrap();
+
+        String::from_utf8(stdout).unwrap()
+    }
+
+    #[test]
+    fn test_console() {
+        assert_eq!(
+            console_session(false),
+            "Wrote image.png\nDecoded: Hidden message\n"
+        );
+    }
+
+    #[test]
+    fn test_console_quiet() {
+        assert_eq!(console_session(true), "Hidden message\n");
+    }
+
+    #[test]
+    fn test_extract_chunks() {
+        use crate::{chunk::Chunk, chunk_type::ChunkType};
+        use std::str::FromStr;
+
+        let chunks = [("IHDR", "header"), ("ruSt", "secret"), ("IEND", "")]
+            .iter()
+            .map(|(chunk_type, data)| {
+                Chunk::new_from_str(ChunkType::from_str(chunk_type).unwrap(), data)
+            })
+            .collect();
+        let png = Png::from_chunks(chunks);
+
+        let dir = temp_path("extract_default");
+        let written = extract_chunks(&png, &dir, false).unwrap();
+        assert_eq!(written, vec![dir.join("1_ruSt.bin")]);
+        assert_eq!(fs::read(&written[0]).unwrap(), b"secret");
+        fs::remove_dir_all(&dir).unwrap();
+
+        let dir = temp_path("extract_standard");
+        let written = extract_chunks(&png, &dir, true).unwrap();
+        assert_eq!(written.len(), 3);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_png_to_stdout() {
+        use crate::{chunk::Chunk, chunk_type::ChunkType};
+        use std::str::FromStr;
+
+        let path = temp_path("stdout_input.png");
+        let mut png = Png::from_chunks(Vec::new());
+        fs::write(&path, png.as_bytes()).unwrap();
+
+        png.append_chunk(Chunk::new_from_str(
+            ChunkType::from_str("ruSt").unwrap(),
+            "secret",
+        ));
+        let mut stdout = Vec::new();
+        write_png(
+            &Destination::Stdout,
+            &png.as_bytes(),
+            WriteOptions::default(),
+            &mut stdout,
+        )
+        .unwrap();
+
+        assert_eq!(stdout, png.as_bytes());
+        assert_eq!(fs::read(&path).unwrap(), Png::STANDARD_HEADER);
+        assert_eq!(Destination::Stdout.path(), None);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_is_stdout_path() {
+        assert!(is_stdout_path(Path::new("-")));
+        assert!(!is_stdout_path(Path::new("./-")));
+        assert!(!is_stdout_path(Path::new("-.png")));
+    }
+
+    #[test]
+    fn test_write_png_preserve_mtime() {
+        use std::time::{Duration, SystemTime};
+
+        let path = temp_path("preserve_mtime.png");
+        fs::write(&path, b"original").unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        let options = WriteOptions {
+            assume_yes: true,
+            preserve_mtime: true,
+        };
+        write_png(
+            &Destination::InPlace(path.clone()),
+            b"replaced",
+            options,
+            &mut io::sink(),
+        )
+        .unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"replaced");
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), modified);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_write_in_place_assume_yes() {
+        let path = temp_path("assume_yes.png");
+        fs::write(&path, b"original").unwrap();
+
+        write_in_place(&path, b"replaced", true).unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"replaced");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_write_in_place_refuses_url() {
+        let url = PathBuf::from("http://127.0.0.1:8766/x.png");
+
+        assert!(matches!(
+            write_in_place(&url, b"replaced", true),
+            Err(OutputError::UrlInPlace(path)) if path == url
+        ));
+
+        for preserve_mtime in [false, true] {
+            let options = WriteOptions {
+                assume_yes: true,
+                preserve_mtime,
+            };
+            let result = write_png(
+                &Destination::InPlace(url.clone()),
+                b"replaced",
+                options,
+                &mut io::sink(),
+            );
+            assert!(matches!(result, Err(OutputError::UrlInPlace(_))));
+        }
+        assert!(!Path::new("http:").exists());
+    }
+}
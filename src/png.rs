@@ -1,10 +1,22 @@
 use core::fmt;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Read};
+use std::ops::ControlFlow;
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 use crate::{
-    chunk::{Chunk, ChunkError, CHUNK_METADATA_NUM_BYTES},
-    chunk_type::{ChunkType, ChunkTypeError},
+    audit::{AuditReport, DuplicateType},
+    chunk::{
+        Chunk, ChunkError, CHUNK_CHECK_NUM_BYTES, CHUNK_LENGTH_NUM_BYTES, CHUNK_METADATA_NUM_BYTES,
+    },
+    chunk_type::{ChunkType, ChunkTypeError, CHUNK_TYPE_NUM_BYTES},
+    format::to_hex,
+    ihdr::{Ihdr, IhdrError, IHDR_NUM_BYTES},
+    text::TextEntry,
 };
 
 #[derive(Error, Debug)]
@@ -18,22 +30,258 @@ pub enum RemoveChunkError {
 pub struct Png {
     header: [u8; 8],
     chunks: Vec<Chunk>,
+    trailing: Vec<u8>,
 }
 
 impl Png {
     pub const STANDARD_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
 
+    #[allow(dead_code)]
+    pub fn new() -> Png {
+        Self::from_chunks(Vec::new())
+    }
+
     pub fn from_chunks(chunks: Vec<Chunk>) -> Png {
         Self {
             header: Self::STANDARD_HEADER,
             chunks,
+            trailing: Vec::new(),
+        }
+    }
+
+    // As with `try_from`, but also records how long each chunk took to parse.
+    pub fn try_from_profiled(value: &[u8]) -> Result<(Png, Vec<Duration>), PngError> {
+        match parse_chunks(value) {
+            (png, timings, None) => Ok((png, timings)),
+            (_, _, Some(err)) => Err(err),
+        }
+    }
+
+    // As with `try_from`, but chunks whose stored CRC doesn't match are kept
+    // rather than rejected, and their indices returned. Every chunk is given
+    // its correct CRC, so writing the image back out repairs them.
+    pub fn try_from_lenient(value: &[u8]) -> Result<(Png, Vec<usize>), PngError> {
+        check_signature(value)?;
+
+        let (read, end) = read_chunks_unchecked(value);
+        let bad_crcs = bad_crc_indices(&read);
+        let mut png = Png::from_chunks(read.into_iter().map(|(chunk, _)| chunk).collect());
+
+        if end < value.len() {
+            let ended = png
+                .last_chunk()
+                .is_some_and(|chunk| *chunk.chunk_type() == ChunkType::IEND);
+            if !ended {
+                return Err(PngError::Unreadable(end));
+            }
+            png.trailing = value[end..].to_vec();
+        }
+
+        Ok((png, bad_crcs))
+    }
+
+    // As with `try_from`, but a damaged file still gives back every chunk read
+    // before the damage, along with the error that stopped parsing there.
+    #[allow(dead_code)]
+    pub fn parse_recoverable(value: &[u8]) -> (Png, Option<PngError>) {
+        let (png, _, err) = parse_chunks(value);
+        (png, err)
+    }
+
+    // As `try_from`, but reading one chunk at a time from e.g. a file, rather
+    // than needing the whole input in memory alongside the parsed chunks.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Png, PngError> {
+        let mut chunks = Vec::new();
+        let mut trailing = read_chunks_from(&mut reader, |chunk| {
+            chunks.push(chunk);
+            ControlFlow::Continue(())
+        })?;
+        reader.read_to_end(&mut trailing)?;
+
+        let mut png = Png::from_chunks(chunks);
+        png.trailing = trailing;
+        Ok(png)
+    }
+
+    // Hands each chunk to `f` as soon as it's read, without keeping any of
+    // them, so that e.g. finding a single chunk can stop once it's found.
+    pub fn for_each_chunk<R: Read, F: FnMut(Chunk) -> ControlFlow<()>>(
+        mut reader: R,
+        f: F,
+    ) -> Result<(), PngError> {
+        read_chunks_from(&mut reader, f).map(|_| ())
+    }
+
+    // Takes raw bytes rather than a parsed `Png`, since a bad signature or CRC
+    // would stop the file being parsed at all. Chunks are read for as long as
+    // they can be, and whatever follows is counted as trailing bytes.
+    pub fn audit(value: &[u8]) -> AuditReport {
+        let (read, end) = read_chunks_unchecked(value);
+        let mut report = AuditReport {
+            signature_ok: value.starts_with(&Png::STANDARD_HEADER),
+            bad_crc_chunks: bad_crc_indices(&read),
+            trailing_bytes_len: value.len() - end,
+            ..Default::default()
+        };
+
+        let png = Png::from_chunks(read.into_iter().map(|(chunk, _)| chunk).collect());
+        report.chunk_count = png.chunks().len();
+        report.ordering_issues = match png.validate() {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.iter().map(|err| err.to_string()).collect(),
+        };
+        report.unknown_types = png
+            .chunk_counts()
+            .into_keys()
+            .filter(|chunk_type| {
+                !ChunkType::from_str(chunk_type)
+                    .map(|chunk_type| chunk_type.is_standard())
+                    .unwrap_or(false)
+            })
+            .collect();
+
+        report
+    }
+
+    // As `validate`, but working from the raw bytes so that a bad signature,
+    // every chunk failing its CRC, and data which can't be read as chunks are
+    // all reported too, rather than parsing giving up at the first.
+    pub fn validate_bytes(value: &[u8]) -> Result<(), Vec<PngError>> {
+        Self::check_bytes(value, true)
+    }
+
+    // Like `validate_bytes`, but skips the checks on which chunks come where,
+    // while still making sure the input is a PNG that can be read to the end.
+    pub fn validate_checksums(value: &[u8]) -> Result<(), Vec<PngError>> {
+        Self::check_bytes(value, false)
+    }
+
+    fn check_bytes(value: &[u8], check_structure: bool) -> Result<(), Vec<PngError>> {
+        let mut errors = Vec::new();
+
+        if let Err(err) = check_signature(value) {
+            errors.push(err);
+        }
+
+        let (read, end) = read_chunks_unchecked(value);
+        errors.extend(bad_crc_indices(&read).into_iter().map(PngError::BadCrc));
+
+        let png = Png::from_chunks(read.into_iter().map(|(chunk, _)| chunk).collect());
+        // Anything after IEND is kept as trailing data, so isn't a problem.
+        let ended = png
+            .last_chunk()
+            .is_some_and(|chunk| chunk.type_eq_str("IEND"));
+        if end < value.len() && !ended {
+            errors.push(PngError::Unreadable(end));
+        }
+
+        if check_structure {
+            if let Err(structure) = png.validate() {
+                errors.extend(structure);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    // Reads only the signature and the IHDR chunk, leaving the rest of the
+    // input untouched, for when the image header is all that's needed.
+    pub fn read_ihdr<R: Read>(mut reader: R) -> Result<Ihdr, PngError> {
+        let mut bytes =
+            [0; Self::STANDARD_HEADER.len() + CHUNK_METADATA_NUM_BYTES + IHDR_NUM_BYTES];
+        reader
+            .read_exact(&mut bytes)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::UnexpectedEof => PngError::InsufficientBytes,
+                _ => PngError::Io(err),
+            })?;
+
+        check_signature(&bytes)?;
+        let chunk_bytes = &bytes[Self::STANDARD_HEADER.len()..];
+
+        let chunk = Chunk::try_from(chunk_bytes)?;
+        if !chunk.type_eq_str("IHDR") {
+            return Err(PngError::FirstChunkNotHeader(
+                chunk.chunk_type().to_string(),
+            ));
         }
+
+        Ok(Ihdr::try_from(chunk.data())?)
     }
 
     pub fn append_chunk(&mut self, chunk: Chunk) {
         self.chunks.push(chunk);
     }
 
+    // Adds the chunk just before IEND if there is one, so chained calls can
+    // build up a complete image in order.
+    #[allow(dead_code)]
+    pub fn with_chunk(mut self, chunk: Chunk) -> Png {
+        match self.last_chunk() {
+            Some(last) if last.type_eq_str("IEND") => {
+                self.chunks.insert(self.chunks.len() - 1, chunk)
+            }
+            _ => self.chunks.push(chunk),
+        }
+        self
+    }
+
+    // Panics if `index` is greater than the number of chunks, as with
+    // `Vec::insert`; an index equal to it appends the chunk.
+    #[allow(dead_code)]
+    pub fn insert_chunk(&mut self, index: usize, chunk: Chunk) {
+        self.chunks.insert(index, chunk);
+    }
+
+    // Partitions the chunks into those of `chunk_type` and the rest, keeping
+    // their order. Any trailing bytes stay with the rest. Neither image need be
+    // valid on its own.
+    #[allow(dead_code)]
+    pub fn split_by_type(mut self, chunk_type: &str) -> (Png, Png) {
+        let trailing = std::mem::take(&mut self.trailing);
+        let (matching, rest): (Vec<Chunk>, Vec<Chunk>) = self
+            .into_chunks()
+            .into_iter()
+            .partition(|chunk| chunk.type_eq_str(chunk_type));
+
+        let mut rest = Png::from_chunks(rest);
+        rest.trailing = trailing;
+        (Png::from_chunks(matching), rest)
+    }
+
+    // Places the chunk straight after the last chunk of type `after`, keeping
+    // it grouped with any run of chunks of that type, such as IDAT.
+    pub fn insert_chunk_after_type(&mut self, after: &str, chunk: Chunk) -> Result<(), PngError> {
+        let idx = self
+            .chunks
+            .iter()
+            .rposition(|existing| existing.type_eq_str(after))
+            .ok_or_else(|| PngError::MissingChunkType(after.to_string()))?;
+
+        self.chunks.insert(idx + 1, chunk);
+        Ok(())
+    }
+
+    // Splices the chunks in so that the first lands at `index`, shifting the
+    // rest of the list along once rather than once per chunk. An index equal to
+    // the number of chunks appends them.
+    #[allow(dead_code)]
+    pub fn insert_chunks_at(&mut self, index: usize, chunks: Vec<Chunk>) -> Result<(), PngError> {
+        if index > self.chunks.len() {
+            return Err(PngError::IndexOutOfBounds {
+                index,
+                len: self.chunks.len(),
+            });
+        }
+
+        self.chunks.splice(index..index, chunks);
+        Ok(())
+    }
+
     pub fn remove_first_chunk(&mut self, chunk_type: &str) -> Result<Chunk, RemoveChunkError> {
         let test_chunk = ChunkType::from_str(chunk_type)?;
         if let Some(index) = self
@@ -47,6 +295,115 @@ impl Png {
         }
     }
 
+    // Removes the chunk at `index` as listed by the `chunks` command, for when
+    // it isn't the first of its type.
+    pub fn remove_chunk_by_index(&mut self, index: usize) -> Result<Chunk, PngError> {
+        if index >= self.chunks.len() {
+            return Err(PngError::IndexOutOfBounds {
+                index,
+                len: self.chunks.len(),
+            });
+        }
+
+        Ok(self.chunks.remove(index))
+    }
+
+    // Unlike `remove_first_chunk`, finding nothing to remove isn't an error.
+    pub fn remove_all_chunks(&mut self, chunk_type: &str) -> usize {
+        let original_len = self.chunks.len();
+        self.chunks.retain(|chunk| !chunk.type_eq_str(chunk_type));
+        original_len - self.chunks.len()
+    }
+
+    pub fn remove_chunks_of_types(&mut self, chunk_types: &[ChunkType]) -> usize {
+        let original_len = self.chunks.len();
+        self.chunks
+            .retain(|chunk| !chunk_types.contains(chunk.chunk_type()));
+        original_len - self.chunks.len()
+    }
+
+    // Keeps only the last tEXt chunk for each keyword, as later chunks are
+    // assumed to supersede earlier ones.
+    pub fn deduplicate_text_by_keyword(&mut self) -> usize {
+        let mut seen_keywords = HashSet::new();
+        let mut keep = vec![true; self.chunks.len()];
+
+        for (idx, chunk) in self.chunks.iter().enumerate().rev() {
+            if *chunk.chunk_type() != ChunkType::TEXT {
+                continue;
+            }
+
+            if let Ok(entry) = TextEntry::try_from(chunk.data()) {
+                keep[idx] = seen_keywords.insert(entry.keyword().to_string());
+            }
+        }
+
+        let original_len = self.chunks.len();
+        let mut keep = keep.into_iter();
+        self.chunks.retain(|_| keep.next().unwrap_or(true));
+        original_len - self.chunks.len()
+    }
+
+    // Discards any chunks after IEND along with any trailing bytes, returning
+    // how many of each were removed as (chunks, bytes).
+    pub fn strip_after_iend(&mut self) -> (usize, usize) {
+        let end = self
+            .chunks
+            .iter()
+            .position(|chunk| *chunk.chunk_type() == ChunkType::IEND)
+            .map_or(self.chunks.len(), |idx| idx + 1);
+
+        let removed_chunks = self.chunks.drain(end..).count();
+        let removed_bytes = self.trailing.len();
+        self.trailing.clear();
+
+        (removed_chunks, removed_bytes)
+    }
+
+    // Replaces the value of each tEXt chunk with the result of calling `f` with
+    // its keyword and current value.
+    #[allow(dead_code)]
+    pub fn map_text_chunks<F: FnMut(&str, &str) -> String>(&mut self, mut f: F) {
+        for chunk in self.chunks.iter_mut() {
+            if *chunk.chunk_type() != ChunkType::TEXT {
+                continue;
+            }
+
+            let Ok(entry) = TextEntry::try_from(chunk.data()) else {
+                continue;
+            };
+
+            let value = f(entry.keyword(), entry.value());
+            let entry = TextEntry::new(entry.keyword(), &value);
+
+            *chunk = Chunk::new(*chunk.chunk_type(), entry.as_bytes());
+        }
+    }
+
+    // Replacements take the place of the first removed chunk, or are appended
+    // if nothing matched.
+    #[allow(dead_code)]
+    pub fn replace_all_chunks_of_type(
+        &mut self,
+        chunk_type: &str,
+        new_chunks: Vec<Chunk>,
+    ) -> usize {
+        let is_match = |chunk: &Chunk| chunk.type_eq_str(chunk_type);
+
+        let position = self
+            .chunks
+            .iter()
+            .position(is_match)
+            .unwrap_or(self.chunks.len());
+
+        let original_len = self.chunks.len();
+        self.chunks.retain(|chunk| !is_match(chunk));
+        let removed = original_len - self.chunks.len();
+
+        self.chunks.splice(position..position, new_chunks);
+        removed
+    }
+
     #[allow(dead_code)]
     pub fn header(&self) -> &[u8; 8] {
         &self.header
@@ -57,72 +414,531 @@ impl Png {
         self.chunks.as_slice()
     }
 
+    #[allow(dead_code)]
+    pub fn into_chunks(self) -> Vec<Chunk> {
+        self.chunks
+    }
+
+    // Offsets are from the start of the serialized file, so the first chunk is
+    // found just after the header.
+    pub fn chunk_iter_with_offsets(&self) -> impl Iterator<Item = (usize, &Chunk)> {
+        self.chunks.iter().scan(self.header.len(), |offset, chunk| {
+            let start = *offset;
+            *offset += chunk.total_bytes();
+            Some((start, chunk))
+        })
+    }
+
+    // Where the chunk at `index` starts in the serialized file.
+    #[allow(dead_code)]
+    pub fn byte_offset_of(&self, index: usize) -> Option<usize> {
+        self.chunk_iter_with_offsets()
+            .nth(index)
+            .map(|(offset, _)| offset)
+    }
+
+    #[allow(dead_code)]
+    pub fn chunk_offsets(&self) -> Vec<usize> {
+        self.chunk_iter_with_offsets()
+            .map(|(offset, _)| offset)
+            .collect()
+    }
+
+    // Bytes following the IEND chunk which don't form valid chunks.
+    pub fn trailing_bytes(&self) -> &[u8] {
+        self.trailing.as_slice()
+    }
+
+    // The number of chunks, not bytes.
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn first_chunk(&self) -> Option<&Chunk> {
+        self.chunks.first()
+    }
+
+    pub fn last_chunk(&self) -> Option<&Chunk> {
+        self.chunks.last()
+    }
+
     pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
-        let test_chunk = ChunkType::from_str(chunk_type).ok()?;
-        self.chunks.iter().find(|x| x.chunk_type() == &test_chunk)
+        self.chunks.iter().find(|x| x.type_eq_str(chunk_type))
     }
 
-    pub fn as_bytes(&self) -> Vec<u8> {
-        // TODO consider reserving the memory for this
-        let mut data: Vec<u8> = self.header.to_vec();
+    // Empty, rather than an error, when nothing matches.
+    pub fn chunks_by_type(&self, chunk_type: &str) -> Vec<&Chunk> {
+        self.chunks
+            .iter()
+            .filter(|x| x.type_eq_str(chunk_type))
+            .collect()
+    }
+
+    #[allow(dead_code)]
+    pub fn chunk_by_crc(&self, crc: u32) -> Option<&Chunk> {
+        self.chunks.iter().find(|x| x.crc_matches(crc))
+    }
+
+    // Picks a random ancillary type not already used by any chunk, so each
+    // hidden message can be given its own type.
+    #[allow(dead_code)]
+    pub fn unused_ancillary_type<R: Rng + ?Sized>(&self, rng: &mut R) -> ChunkType {
+        let used = self.chunk_types_set();
+
+        loop {
+            let chunk_type = ChunkType::random_ancillary(rng);
+            if !used.contains(&chunk_type) {
+                return chunk_type;
+            }
+        }
+    }
+
+    // Every keyword/value pair held in tEXt, zTXt and iTXt chunks, in file
+    // order. Malformed text chunks are skipped.
+    #[allow(dead_code)]
+    pub fn iter_text(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        self.chunks.iter().filter_map(|chunk| {
+            let entry = match *chunk.chunk_type() {
+                ChunkType::TEXT => TextEntry::try_from(chunk.data()),
+                ChunkType::ZTXT => TextEntry::from_ztxt(chunk.data()),
+                ChunkType::ITXT => TextEntry::from_itxt(chunk.data()),
+                _ => return None,
+            };
+
+            entry
+                .ok()
+                .map(|entry| (entry.keyword().to_string(), entry.value().to_string()))
+        })
+    }
+
+    // Every distinct chunk type present, for checking several types without
+    // searching the chunks each time.
+    pub fn chunk_types_set(&self) -> HashSet<ChunkType> {
+        self.chunks
+            .iter()
+            .map(|chunk| *chunk.chunk_type())
+            .collect()
+    }
+
+    pub fn chunk_counts(&self) -> BTreeMap<String, usize> {
+        self.count_by(|chunk| chunk.chunk_type().to_string())
+    }
+
+    // Groups the chunks by an arbitrary key, counting how many fall under each.
+    pub fn count_by<K: Ord, F: FnMut(&Chunk) -> K>(&self, mut key: F) -> BTreeMap<K, usize> {
+        let mut counts = BTreeMap::new();
         for chunk in &self.chunks {
-            data.extend(chunk.as_bytes());
+            *counts.entry(key(chunk)).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    // Chunks of types not defined by the spec, where hidden data usually lives,
+    // optionally only those whose entropy exceeds `min_entropy` bits per byte.
+    pub fn detect_suspicious(&self, min_entropy: Option<f64>) -> Vec<(usize, &Chunk)> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| !chunk.chunk_type().is_standard())
+            .filter(|(_, chunk)| match min_entropy {
+                Some(min) => chunk.entropy() > min,
+                None => true,
+            })
+            .collect()
+    }
+
+    // Chunk types appearing more than once, flagging those the spec says must
+    // be unique.
+    pub fn duplicate_types(&self) -> Vec<DuplicateType> {
+        self.chunk_counts()
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(chunk_type, count)| DuplicateType {
+                violation: ChunkType::from_str(&chunk_type)
+                    .map(|chunk_type| chunk_type.must_be_unique())
+                    .unwrap_or(false),
+                chunk_type,
+                count,
+            })
+            .collect()
+    }
+
+    // Checks the overall structure of the image, reporting every problem found
+    // rather than just the first.
+    pub fn validate(&self) -> Result<(), Vec<PngError>> {
+        let mut errors = Vec::new();
+
+        match (self.first_chunk(), self.last_chunk()) {
+            (Some(first), Some(last)) => {
+                if *first.chunk_type() != ChunkType::IHDR {
+                    errors.push(PngError::FirstChunkNotHeader(
+                        first.chunk_type().to_string(),
+                    ));
+                }
+
+                if *last.chunk_type() != ChunkType::IEND {
+                    errors.push(PngError::LastChunkNotEnd(last.chunk_type().to_string()));
+                } else if last.length() != 0 {
+                    errors.push(PngError::NonEmptyEnd(last.length()));
+                }
+            }
+            _ => errors.push(PngError::NoChunks),
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
         }
+    }
+
+    // A SHA-256 fingerprint of the file exactly as it would be written out.
+    pub fn digest(&self) -> [u8; 32] {
+        Sha256::digest(self.as_bytes()).into()
+    }
+
+    // As with `digest`, but only covering the data of chunks of one type, so
+    // that e.g. images differing only in their metadata match on IDAT.
+    pub fn digest_of_type(&self, chunk_type: &str) -> [u8; 32] {
+        Sha256::digest(self.chunk_data_concat(chunk_type)).into()
+    }
+
+    // The data of every chunk of the given type joined together in order, as
+    // IDAT data must be before it can be decompressed.
+    pub fn chunk_data_concat(&self, chunk_type: &str) -> Vec<u8> {
+        self.chunks
+            .iter()
+            .filter(|chunk| chunk.type_eq_str(chunk_type))
+            .flat_map(|chunk| chunk.data().iter().copied())
+            .collect()
+    }
+
+    // The size `as_bytes` would give, trailing data included, without building
+    // it, e.g. to check a size budget before adding a payload.
+    pub fn total_bytes(&self) -> usize {
+        self.header.len()
+            + self.chunks.iter().map(Chunk::total_bytes).sum::<usize>()
+            + self.trailing.len()
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::with_capacity(self.total_bytes());
+        data.extend(self.header);
+        data.extend(self.chunks_as_bytes());
+        data.extend(&self.trailing);
         data
     }
+
+    // Where re-serializing the image first differs from the bytes it was parsed
+    // from, or None if they match exactly.
+    pub fn first_difference_from(&self, original: &[u8]) -> Option<usize> {
+        let bytes = self.as_bytes();
+        match bytes.iter().zip(original).position(|(a, b)| a != b) {
+            Some(offset) => Some(offset),
+            None if bytes.len() != original.len() => Some(bytes.len().min(original.len())),
+            None => None,
+        }
+    }
+
+    // Just the chunks, without the signature or any trailing bytes, for
+    // embedding the chunk stream in some other container.
+    pub fn chunks_as_bytes(&self) -> Vec<u8> {
+        self.chunks
+            .iter()
+            .flat_map(|chunk| chunk.as_bytes())
+            .collect()
+    }
+}
+
+// A mismatch in whatever bytes are present is reported before a lack of them,
+// so that a short file which isn't a PNG at all says so.
+fn check_signature(bytes: &[u8]) -> Result<(), PngError> {
+    let header_len = Png::STANDARD_HEADER.len();
+    let found = &bytes[..bytes.len().min(header_len)];
+
+    if found != &Png::STANDARD_HEADER[..found.len()] {
+        return Err(PngError::InvalidSignature {
+            found: found.to_vec(),
+        });
+    }
+    if found.len() < header_len {
+        return Err(PngError::InsufficientBytes);
+    }
+    Ok(())
+}
+
+pub fn find_png_start(bytes: &[u8]) -> Option<usize> {
+    bytes
+        .windows(Png::STANDARD_HEADER.len())
+        .position(|window| window == Png::STANDARD_HEADER)
 }
 
 #[derive(Error, Debug)]
 pub enum PngError {
     #[error("Insufficient bytes")]
     InsufficientBytes,
-    #[error("Bytes are missing PNG standard header.")]
-    NoStandardHeader,
+    #[error(
+        "Bytes are missing PNG standard header: expected {} but found {}.",
+        to_hex(&Png::STANDARD_HEADER),
+        to_hex(.found)
+    )]
+    InvalidSignature { found: Vec<u8> },
     #[error(transparent)]
     BadChunk(#[from] ChunkError),
+    #[error("{fill}-filled chunk type suggests padding or truncation at offset {offset}.")]
+    PaddedChunkType { fill: &'static str, offset: usize },
+    #[error("No chunks present; missing IHDR/IEND.")]
+    NoChunks,
+    #[error("First chunk should be IHDR but was {0}.")]
+    FirstChunkNotHeader(String),
+    #[error("Last chunk should be IEND but was {0}.")]
+    LastChunkNotEnd(String),
+    #[error("IEND chunk should be empty but holds {0} bytes.")]
+    NonEmptyEnd(u32),
+    #[error("No {0} chunk found.")]
+    MissingChunkType(String),
+    #[error("Chunk {0} failed its checksum.")]
+    BadCrc(usize),
+    #[error("Couldn't read a chunk at offset {0}.")]
+    Unreadable(usize),
+    #[error("Chunk index {index} is out of bounds for {len} chunks.")]
+    IndexOutOfBounds { index: usize, len: usize },
+    #[error(transparent)]
+    BadHeader(#[from] IhdrError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
 }
 
-impl TryFrom<&[u8]> for Png {
-    type Error = PngError;
+// Parses chunks until the end of the input or the first one which fails,
+// keeping every chunk up to that point and how long each took.
+fn parse_chunks(value: &[u8]) -> (Png, Vec<Duration>, Option<PngError>) {
+    let mut chunks = Vec::<Chunk>::new();
+    let mut timings = Vec::<Duration>::new();
 
-    fn try_from(value: &[u8]) -> std::result::Result<Self, Self::Error> {
-        let header_len: usize = Png::STANDARD_HEADER.len();
+    if let Err(err) = check_signature(value) {
+        return (Png::from_chunks(chunks), timings, Some(err));
+    }
 
-        if value.len() < header_len {
-            return Err(PngError::InsufficientBytes);
-        }
+    let mut byte_index = Png::STANDARD_HEADER.len();
+
+    let mut trailing = Vec::new();
+    let mut seen_end = false;
+    let mut error = None;
+
+    while byte_index < value.len() {
+        let bytes = &value[byte_index..];
+        let start = Instant::now();
+        let chunk = match Chunk::try_from(bytes) {
+            Ok(chunk) => chunk,
+            // Data appended after the end of the image is kept verbatim
+            // rather than treated as a malformed chunk.
+            Err(_) if seen_end => {
+                trailing = bytes.to_vec();
+                break;
+            }
+            Err(err) => {
+                error = Some(chunk_error_at(value, byte_index, err));
+                break;
+            }
+        };
+        timings.push(start.elapsed());
+        byte_index += chunk.total_bytes();
+        seen_end |= *chunk.chunk_type() == ChunkType::IEND;
+        chunks.push(chunk);
+    }
 
-        let mut chunks = Vec::<Chunk>::new();
+    let mut png = Png::from_chunks(chunks);
+    png.trailing = trailing;
+    (png, timings, error)
+}
 
-        let mut byte_index = header_len;
+// Reads a chunk without checking it against its CRC, returning it along with
+// the CRC stored in the file.
+fn read_chunk_unchecked(bytes: &[u8]) -> Option<(Chunk, u32)> {
+    let length_bytes = bytes.get(..CHUNK_LENGTH_NUM_BYTES)?;
+    let length = u32::from_be_bytes(length_bytes.try_into().ok()?) as usize;
 
-        let header = &value[..header_len];
+    let type_end = CHUNK_LENGTH_NUM_BYTES + CHUNK_TYPE_NUM_BYTES;
+    let type_bytes: [u8; CHUNK_TYPE_NUM_BYTES] = bytes
+        .get(CHUNK_LENGTH_NUM_BYTES..type_end)?
+        .try_into()
+        .ok()?;
+    let chunk_type = ChunkType::new(type_bytes).ok()?;
 
-        if header != Png::STANDARD_HEADER {
-            return Err(PngError::NoStandardHeader);
-        }
+    let data_end = type_end.checked_add(length)?;
+    let data = bytes.get(type_end..data_end)?;
+    let crc_bytes = bytes.get(data_end..data_end + CHUNK_CHECK_NUM_BYTES)?;
+    let stored_crc = u32::from_be_bytes(crc_bytes.try_into().ok()?);
 
-        while byte_index < value.len() {
-            let bytes = &value[byte_index..];
-            let chunk = Chunk::try_from(bytes)?;
-            byte_index += chunk.length() as usize + CHUNK_METADATA_NUM_BYTES;
-            chunks.push(chunk);
+    Some((Chunk::new(chunk_type, data.to_vec()), stored_crc))
+}
+
+// Reads chunks following the signature for as long as possible, returning
+// each with its stored CRC, along with the offset where reading stopped.
+fn read_chunks_unchecked(value: &[u8]) -> (Vec<(Chunk, u32)>, usize) {
+    let mut chunks = Vec::new();
+    let mut byte_index = Png::STANDARD_HEADER.len().min(value.len());
+
+    while let Some((chunk, stored_crc)) = read_chunk_unchecked(&value[byte_index..]) {
+        byte_index += chunk.total_bytes();
+        chunks.push((chunk, stored_crc));
+    }
+
+    (chunks, byte_index)
+}
+
+// Reads the next chunk, leaving its raw bytes in `buf` in case they turn out to
+// be trailing data. Returns `None` if the reader is already at its end.
+fn read_chunk_from<R: Read>(reader: &mut R, buf: &mut Vec<u8>) -> Result<Option<Chunk>, PngError> {
+    buf.clear();
+    let prefix_len = CHUNK_LENGTH_NUM_BYTES + CHUNK_TYPE_NUM_BYTES;
+    reader.by_ref().take(prefix_len as u64).read_to_end(buf)?;
+    if buf.is_empty() {
+        return Ok(None);
+    }
+
+    if let Some(length_bytes) = buf.get(..CHUNK_LENGTH_NUM_BYTES) {
+        let length = u32::from_be_bytes(length_bytes.try_into().unwrap()) as u64;
+        // Taking rather than allocating the stated length up front means a
+        // corrupt length can't claim gigabytes of memory.
+        reader
+            .by_ref()
+            .take(length + CHUNK_CHECK_NUM_BYTES as u64)
+            .read_to_end(buf)?;
+    }
+
+    // Whatever was read, complete or not, is checked just as a slice would be.
+    Chunk::try_from(buf.as_slice())
+        .map(Some)
+        .map_err(|err| chunk_error_at(buf, 0, err))
+}
+
+// Reads the signature then each chunk in turn, handing it to `f`. As with
+// `try_from`, data after IEND which can't be read as a chunk isn't an error;
+// the bytes read of it so far are returned, and the rest left in the reader.
+fn read_chunks_from<R: Read, F: FnMut(Chunk) -> ControlFlow<()>>(
+    reader: &mut R,
+    mut f: F,
+) -> Result<Vec<u8>, PngError> {
+    let mut signature = Vec::new();
+    reader
+        .by_ref()
+        .take(Png::STANDARD_HEADER.len() as u64)
+        .read_to_end(&mut signature)?;
+    check_signature(&signature)?;
+
+    let mut buf = Vec::new();
+    let mut byte_index = Png::STANDARD_HEADER.len();
+    let mut seen_end = false;
+
+    loop {
+        let chunk = match read_chunk_from(reader, &mut buf) {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => return Ok(Vec::new()),
+            Err(PngError::Io(err)) => return Err(PngError::Io(err)),
+            Err(_) if seen_end => return Ok(buf),
+            Err(PngError::PaddedChunkType { fill, .. }) => {
+                return Err(PngError::PaddedChunkType {
+                    fill,
+                    offset: byte_index,
+                })
+            }
+            Err(err) => return Err(err),
+        };
+
+        byte_index += buf.len();
+        seen_end |= *chunk.chunk_type() == ChunkType::IEND;
+        if f(chunk).is_break() {
+            return Ok(Vec::new());
         }
+    }
+}
+
+fn bad_crc_indices(chunks: &[(Chunk, u32)]) -> Vec<usize> {
+    chunks
+        .iter()
+        .enumerate()
+        .filter(|(_, (chunk, stored_crc))| !chunk.crc_matches(*stored_crc))
+        .map(|(idx, _)| idx)
+        .collect()
+}
 
-        Ok(Png::from_chunks(chunks))
+fn chunk_error_at(bytes: &[u8], offset: usize, err: ChunkError) -> PngError {
+    match (&err, padding_fill(bytes, offset)) {
+        (ChunkError::BadType(_), Some(fill)) => PngError::PaddedChunkType { fill, offset },
+        _ => PngError::BadChunk(err),
     }
 }
 
-impl fmt::Display for Png {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+// Distinguishes a type region that was never written from one that is merely
+// invalid, since the former usually means the file was padded or cut short.
+fn padding_fill(bytes: &[u8], offset: usize) -> Option<&'static str> {
+    let type_start = offset + CHUNK_LENGTH_NUM_BYTES;
+    let type_bytes = bytes.get(type_start..type_start + CHUNK_TYPE_NUM_BYTES)?;
+
+    if type_bytes.iter().all(|byte| *byte == 0) {
+        Some("Zero")
+    } else if type_bytes.iter().all(|byte| byte.is_ascii_whitespace()) {
+        Some("Whitespace")
+    } else {
+        None
+    }
+}
+
+impl Default for Png {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TryFrom<&[u8]> for Png {
+    type Error = PngError;
+
+    fn try_from(value: &[u8]) -> std::result::Result<Self, Self::Error> {
+        Png::try_from_profiled(value).map(|(png, _)| png)
+    }
+}
+
+impl Png {
+    // As the `Display` output, but with every chunk shown as by
+    // `Chunk::display_lossy`.
+    pub fn display_lossy(&self) -> LossyPng<'_> {
+        LossyPng(self)
+    }
+
+    fn write_display(&self, f: &mut fmt::Formatter<'_>, lossy: bool) -> fmt::Result {
         writeln!(f, "{} chunks:", self.chunks.len())?;
         for (idx, chunk) in self.chunks.iter().enumerate() {
-            write!(f, "\tchunk {}\n{}\n\n", idx, chunk)?;
+            if lossy {
+                write!(f, "\tchunk {}\n{}\n\n", idx, chunk.display_lossy())?;
+            } else {
+                write!(f, "\tchunk {}\n{}\n\n", idx, chunk)?;
+            }
         }
         Ok(())
     }
 }
 
+impl fmt::Display for Png {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_display(f, false)
+    }
+}
+
+pub struct LossyPng<'a>(&'a Png);
+
+impl fmt::Display for LossyPng<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.write_display(f, true)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -197,10 +1013,35 @@ mod tests {
     }
 
     #[test]
-    fn test_invalid_chunk() {
-        let mut chunk_bytes: Vec<u8> = testing_chunks()
-            .into_iter()
-            .flat_map(|chunk| chunk.as_bytes())
+    fn test_invalid_signature_message() {
+        // The start of a JPEG file.
+        let bytes = [0xff, 0xd8, 0xff, 0xe0, 0, 16, 74, 70, 73, 70, 0, 1];
+
+        let Err(err) = Png::try_from(&bytes[..]) else {
+            panic!("a JPEG header should not parse as a PNG");
+        };
+
+        assert!(matches!(err, PngError::InvalidSignature { .. }));
+        assert_eq!(
+            err.to_string(),
+            "Bytes are missing PNG standard header: expected 89504e470d0a1a0a but found ffd8ffe000104a46."
+        );
+    }
+
+    #[test]
+    fn test_short_input_signature() {
+        let png = Png::try_from(&Png::STANDARD_HEADER[..4]);
+        assert!(matches!(png, Err(PngError::InsufficientBytes)));
+
+        let png = Png::try_from(&b"GIF"[..]);
+        assert!(matches!(png, Err(PngError::InvalidSignature { .. })));
+    }
+
+    #[test]
+    fn test_invalid_chunk() {
+        let mut chunk_bytes: Vec<u8> = testing_chunks()
+            .into_iter()
+            .flat_map(|chunk| chunk.as_bytes())
             .collect();
 
         #[rustfmt::skip]
@@ -218,6 +1059,36 @@ mod tests {
         assert!(png.is_err());
     }
 
+    #[test]
+    fn test_zero_filled_chunk_type() {
+        let chunk_bytes: Vec<u8> = testing_chunks()
+            .into_iter()
+            .flat_map(|chunk| chunk.as_bytes())
+            .collect();
+
+        let bytes: Vec<u8> = Png::STANDARD_HEADER
+            .iter()
+            .chain(chunk_bytes.iter())
+            .chain([0; 16].iter())
+            .copied()
+            .collect();
+
+        let err = Png::try_from(bytes.as_ref()).err().unwrap();
+
+        let expected_offset = Png::STANDARD_HEADER.len() + chunk_bytes.len();
+        assert!(matches!(
+            err,
+            PngError::PaddedChunkType { fill: "Zero", offset } if offset == expected_offset
+        ));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Zero-filled chunk type suggests padding or truncation at offset {}.",
+                expected_offset
+            )
+        );
+    }
+
     #[test]
     fn test_list_chunks() {
         let png = testing_png();
@@ -225,6 +1096,17 @@ mod tests {
         assert_eq!(chunks.len(), 3);
     }
 
+    #[test]
+    fn test_first_and_last_chunk() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(&png.first_chunk().unwrap().chunk_type().to_string(), "IHDR");
+        assert_eq!(&png.last_chunk().unwrap().chunk_type().to_string(), "IEND");
+
+        let png = Png::from_chunks(Vec::new());
+        assert!(png.first_chunk().is_none());
+        assert!(png.last_chunk().is_none());
+    }
+
     #[test]
     fn test_chunk_by_type() {
         let png = testing_png();
@@ -233,6 +1115,31 @@ mod tests {
         assert_eq!(&chunk.data_as_string().unwrap(), "I am the first chunk");
     }
 
+    #[test]
+    fn test_chunks_by_type() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("miDl", "Another middle").unwrap());
+
+        let chunks = png.chunks_by_type("miDl");
+        let messages: Vec<String> = chunks
+            .iter()
+            .map(|chunk| chunk.data_as_string().unwrap())
+            .collect();
+        assert_eq!(messages, ["I am another chunk", "Another middle"]);
+
+        assert!(png.chunks_by_type("tEXt").is_empty());
+    }
+
+    #[test]
+    fn test_chunk_by_crc() {
+        let png = testing_png();
+        let crc = testing_chunks()[1].crc();
+
+        let chunk = png.chunk_by_crc(crc).unwrap();
+        assert_eq!(&chunk.chunk_type().to_string(), "miDl");
+        assert!(png.chunk_by_crc(crc.wrapping_add(1)).is_none());
+    }
+
     #[test]
     fn test_append_chunk() {
         let mut png = testing_png();
@@ -251,6 +1158,222 @@ mod tests {
         assert!(chunk.is_none());
     }
 
+    #[test]
+    fn test_remove_chunk_by_index() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("FrSt", "I am another first chunk").unwrap());
+
+        let removed = png.remove_chunk_by_index(3).unwrap();
+        assert_eq!(
+            removed.data_as_string().unwrap(),
+            "I am another first chunk"
+        );
+        assert_eq!(png.chunks().len(), 3);
+        assert_eq!(
+            png.chunk_by_type("FrSt").unwrap().data_as_string().unwrap(),
+            "I am the first chunk"
+        );
+
+        assert!(matches!(
+            png.remove_chunk_by_index(3),
+            Err(PngError::IndexOutOfBounds { index: 3, len: 3 })
+        ));
+        assert_eq!(png.chunks().len(), 3);
+    }
+
+    #[test]
+    fn test_remove_all_chunks() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("FrSt", "I am another first chunk").unwrap());
+
+        assert_eq!(png.remove_all_chunks("FrSt"), 2);
+        assert_eq!(png.chunks().len(), 2);
+        assert!(png.chunk_by_type("FrSt").is_none());
+
+        assert_eq!(png.remove_all_chunks("FrSt"), 0);
+        assert_eq!(png.chunks().len(), 2);
+    }
+
+    #[test]
+    fn test_remove_chunks_of_types() {
+        use std::str::FromStr;
+
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("FrSt", "I am another first chunk").unwrap());
+
+        let removed = png.remove_chunks_of_types(&[
+            ChunkType::from_str("FrSt").unwrap(),
+            ChunkType::from_str("LASt").unwrap(),
+        ]);
+
+        assert_eq!(removed, 3);
+        assert_eq!(png.chunks().len(), 1);
+        assert_eq!(&png.chunks()[0].chunk_type().to_string(), "miDl");
+    }
+
+    #[test]
+    fn test_deduplicate_text_by_keyword() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("tEXt", "Author\0First").unwrap());
+        png.append_chunk(chunk_from_strings("tEXt", "Title\0Dice").unwrap());
+        png.append_chunk(chunk_from_strings("tEXt", "Author\0Latest").unwrap());
+
+        let removed = png.deduplicate_text_by_keyword();
+
+        assert_eq!(removed, 1);
+        assert_eq!(png.chunks().len(), 5);
+
+        let text: Vec<String> = png
+            .chunks()
+            .iter()
+            .filter(|chunk| chunk.chunk_type().to_string() == "tEXt")
+            .map(|chunk| chunk.data_as_string().unwrap())
+            .collect();
+        assert_eq!(text, vec!["Title\0Dice", "Author\0Latest"]);
+    }
+
+    #[test]
+    fn test_map_text_chunks() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("tEXt", "Author\0Someone").unwrap());
+        png.append_chunk(chunk_from_strings("tEXt", "Title\0Dice").unwrap());
+
+        png.map_text_chunks(|_, value| value.to_uppercase());
+
+        let png = Png::try_from(png.as_bytes().as_ref()).unwrap();
+        let text: Vec<String> = png
+            .chunks()
+            .iter()
+            .filter(|chunk| chunk.chunk_type().to_string() == "tEXt")
+            .map(|chunk| chunk.data_as_string().unwrap())
+            .collect();
+        assert_eq!(text, vec!["Author\0SOMEONE", "Title\0DICE"]);
+        assert_eq!(
+            &png.chunk_by_type("FrSt").unwrap().data_as_string().unwrap(),
+            "I am the first chunk"
+        );
+    }
+
+    #[test]
+    fn test_replace_all_chunks_of_type() {
+        let mut png = testing_png();
+        png.chunks
+            .insert(1, chunk_from_strings("tEXt", "Author\0Someone").unwrap());
+        png.append_chunk(chunk_from_strings("tEXt", "Title\0Something").unwrap());
+
+        let removed = png.replace_all_chunks_of_type(
+            "tEXt",
+            vec![chunk_from_strings("tEXt", "Comment\0Replaced").unwrap()],
+        );
+
+        assert_eq!(removed, 2);
+
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, vec!["FrSt", "tEXt", "miDl", "LASt"]);
+        assert_eq!(
+            &png.chunk_by_type("tEXt").unwrap().data_as_string().unwrap(),
+            "Comment\0Replaced"
+        );
+    }
+
+    #[test]
+    fn test_replace_all_chunks_of_type_no_match() {
+        let mut png = testing_png();
+
+        let removed = png
+            .replace_all_chunks_of_type("tEXt", vec![chunk_from_strings("tEXt", "New").unwrap()]);
+
+        assert_eq!(removed, 0);
+        assert_eq!(png.chunks().len(), 4);
+        assert_eq!(&png.chunks()[3].chunk_type().to_string(), "tEXt");
+    }
+
+    #[test]
+    fn test_chunk_counts() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("FrSt", "I am another first chunk").unwrap());
+
+        let counts = png.chunk_counts();
+
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts["FrSt"], 2);
+        assert_eq!(counts["miDl"], 1);
+        assert_eq!(counts["LASt"], 1);
+    }
+
+    #[test]
+    fn test_chunk_types_set() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("FrSt", "I am another first chunk").unwrap());
+
+        let types = png.chunk_types_set();
+
+        assert_eq!(types.len(), 3);
+        for chunk_type in ["FrSt", "miDl", "LASt"] {
+            assert!(types.contains(&ChunkType::from_str(chunk_type).unwrap()));
+        }
+        assert!(!types.contains(&ChunkType::IEND));
+    }
+
+    #[test]
+    fn test_count_by_criticality() {
+        let png = testing_png();
+
+        let counts = png.count_by(|chunk| chunk.chunk_type().is_critical());
+
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&true], 2);
+        assert_eq!(counts[&false], 1);
+    }
+
+    #[test]
+    fn test_read_ihdr_dimensions() {
+        let ihdr = Png::read_ihdr(&PNG_FILE[..]).unwrap();
+        assert_eq!(format!("{}x{}", ihdr.width(), ihdr.height()), "50x50");
+    }
+
+    #[test]
+    fn test_read_ihdr_ignores_rest_of_file() {
+        // Cut off partway through the chunk after IHDR, which fails a full parse.
+        let bytes = &PNG_FILE[..40];
+        assert!(Png::try_from(bytes).is_err());
+        assert!(Png::read_ihdr(bytes).is_ok());
+    }
+
+    #[test]
+    fn test_read_ihdr_truncated() {
+        let ihdr = Png::read_ihdr(&PNG_FILE[..20]);
+        assert!(matches!(ihdr, Err(PngError::InsufficientBytes)));
+    }
+
+    #[test]
+    fn test_detect_suspicious() {
+        let png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "").unwrap(),
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), (0..=255).collect()),
+            chunk_from_strings("teXt", "aaaaaaaaaaaaaaaaaaaaaaab").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+
+        let found: Vec<usize> = png
+            .detect_suspicious(None)
+            .iter()
+            .map(|(idx, _)| *idx)
+            .collect();
+        assert_eq!(found, [1, 2]);
+
+        let found: Vec<usize> = png
+            .detect_suspicious(Some(7.0))
+            .iter()
+            .map(|(idx, _)| *idx)
+            .collect();
+        assert_eq!(found, [1]);
+    }
+
     #[test]
     fn test_png_from_image_file() {
         let png = Png::try_from(&PNG_FILE[..]);
@@ -265,6 +1388,716 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_find_png_start() {
+        let junk: [u8; 5] = [1, 2, 3, 137, 80];
+        let bytes: Vec<u8> = junk.iter().chain(PNG_FILE.iter()).copied().collect();
+
+        let start = find_png_start(&bytes).unwrap();
+        assert_eq!(start, junk.len());
+
+        let png = Png::try_from(&bytes[start..]).unwrap();
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_find_png_start_missing() {
+        assert!(find_png_start(&PNG_FILE[1..]).is_none());
+    }
+
+    #[test]
+    fn test_chunk_iter_with_offsets() {
+        let png = testing_png();
+        let offsets: Vec<usize> = png
+            .chunk_iter_with_offsets()
+            .map(|(offset, _)| offset)
+            .collect();
+
+        // Each testing chunk is 12 bytes of metadata plus its message
+        assert_eq!(offsets, vec![8, 8 + 12 + 20, 8 + 12 + 20 + 12 + 18]);
+
+        let bytes = png.as_bytes();
+        for (offset, chunk) in png.chunk_iter_with_offsets() {
+            let serialized = chunk.as_bytes();
+            assert_eq!(&bytes[offset..offset + serialized.len()], serialized);
+        }
+    }
+
+    #[test]
+    fn test_byte_offset_of() {
+        let png = testing_png();
+
+        assert_eq!(png.byte_offset_of(0), Some(8));
+        // The first testing chunk holds 20 bytes of data.
+        assert_eq!(png.byte_offset_of(1), Some(8 + 12 + 20));
+        assert_eq!(png.byte_offset_of(3), None);
+    }
+
+    #[test]
+    fn test_chunk_offsets() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let offsets = png.chunk_offsets();
+
+        assert_eq!(offsets.len(), png.chunks().len());
+        // IHDR follows the signature, and sRGB follows the 13 bytes of IHDR data.
+        assert_eq!(offsets[..2], [8, 8 + 12 + 13]);
+        for (offset, chunk) in offsets.iter().zip(png.chunks()) {
+            assert_eq!(
+                &PNG_FILE[offset + 4..offset + 8],
+                chunk.chunk_type().bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert!(png.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_malformed() {
+        let errors = testing_png().validate().unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(&errors[0], PngError::FirstChunkNotHeader(found) if found == "FrSt"));
+        assert!(matches!(&errors[1], PngError::LastChunkNotEnd(found) if found == "LASt"));
+    }
+
+    #[test]
+    fn test_validate_non_empty_end() {
+        let png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "").unwrap(),
+            chunk_from_strings("IEND", "Not empty").unwrap(),
+        ]);
+
+        let errors = png.validate().unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], PngError::NonEmptyEnd(9)));
+    }
+
+    #[test]
+    fn test_duplicate_types() {
+        let chunks = ["IHDR", "IHDR", "IDAT", "IDAT", "IDAT", "tEXt", "IEND"]
+            .iter()
+            .map(|chunk_type| chunk_from_strings(chunk_type, "").unwrap())
+            .collect();
+        let png = Png::from_chunks(chunks);
+
+        assert_eq!(
+            png.duplicate_types(),
+            vec![
+                DuplicateType {
+                    chunk_type: "IDAT".to_string(),
+                    count: 3,
+                    violation: false,
+                },
+                DuplicateType {
+                    chunk_type: "IHDR".to_string(),
+                    count: 2,
+                    violation: true,
+                },
+            ]
+        );
+        assert!(testing_png().duplicate_types().is_empty());
+    }
+
+    #[test]
+    fn test_validate_no_chunks() {
+        let errors = Png::from_chunks(Vec::new()).validate().unwrap_err();
+        assert!(matches!(errors[..], [PngError::NoChunks]));
+    }
+
+    #[test]
+    fn test_signature_only() {
+        // Parses fine, leaving validation to complain about the missing chunks.
+        let png = Png::try_from(&Png::STANDARD_HEADER[..]).unwrap();
+        assert!(png.is_empty());
+        assert!(png.trailing_bytes().is_empty());
+
+        let errors = png.validate().unwrap_err();
+        assert!(matches!(errors[..], [PngError::NoChunks]));
+        assert_eq!(
+            errors[0].to_string(),
+            "No chunks present; missing IHDR/IEND."
+        );
+    }
+
+    #[test]
+    fn test_total_bytes() {
+        let png = testing_png();
+        assert_eq!(png.total_bytes(), png.as_bytes().len());
+        assert_eq!(png.total_bytes(), 8 + 3 * 12 + 20 + 18 + 19);
+
+        let bytes: Vec<u8> = PNG_FILE.iter().chain(b"trailing").copied().collect();
+        let png = Png::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(png.total_bytes(), bytes.len());
+    }
+
+    #[test]
+    fn test_trailing_bytes() {
+        let payload = b"appended secret payload";
+        let bytes: Vec<u8> = PNG_FILE.iter().chain(payload.iter()).copied().collect();
+
+        let png = Png::try_from(bytes.as_ref()).unwrap();
+
+        assert_eq!(png.trailing_bytes(), payload);
+        assert_eq!(
+            &png.chunks().last().unwrap().chunk_type().to_string(),
+            "IEND"
+        );
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_first_difference_from() {
+        let bytes: Vec<u8> = PNG_FILE.iter().chain(b"trailing").copied().collect();
+        let mut png = Png::try_from(bytes.as_ref()).unwrap();
+
+        assert_eq!(png.first_difference_from(&bytes), None);
+
+        png.strip_after_iend();
+        assert_eq!(png.first_difference_from(&bytes), Some(PNG_FILE.len()));
+
+        let mut changed = bytes.clone();
+        changed[40] ^= 0xFF;
+        let png = Png::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(png.first_difference_from(&changed), Some(40));
+    }
+
+    #[test]
+    fn test_no_trailing_bytes() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert!(png.trailing_bytes().is_empty());
+    }
+
+    #[test]
+    fn test_from_reader_matches_try_from() {
+        let payload = b"appended secret payload";
+        let bytes: Vec<u8> = PNG_FILE.iter().chain(payload.iter()).copied().collect();
+
+        let png = Png::from_reader(bytes.as_slice()).unwrap();
+        let expected = Png::try_from(bytes.as_ref()).unwrap();
+
+        assert_eq!(png.chunks(), expected.chunks());
+        assert_eq!(png.trailing_bytes(), payload);
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_from_reader_invalid() {
+        let result = Png::from_reader(&PNG_FILE[1..]);
+        assert!(matches!(result, Err(PngError::InvalidSignature { .. })));
+
+        let result = Png::from_reader(&PNG_FILE[..PNG_FILE.len() - 2]);
+        assert!(matches!(
+            result,
+            Err(PngError::BadChunk(ChunkError::InsufficientBytes {
+                needed: 12,
+                available: 10
+            }))
+        ));
+
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[33..41].copy_from_slice(&[0; 8]);
+        let result = Png::from_reader(bytes.as_slice());
+        assert!(matches!(
+            result,
+            Err(PngError::PaddedChunkType {
+                fill: "Zero",
+                offset: 33
+            })
+        ));
+    }
+
+    #[test]
+    fn test_for_each_chunk_stops_early() {
+        // Everything after the first chunk is cut off, which only matters if
+        // it's read.
+        let mut types = Vec::new();
+        let result = Png::for_each_chunk(&PNG_FILE[..40], |chunk| {
+            types.push(chunk.chunk_type().to_string());
+            ControlFlow::Break(())
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(types, ["IHDR"]);
+
+        let mut count = 0;
+        Png::for_each_chunk(&PNG_FILE[..], |_| {
+            count += 1;
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+        assert_eq!(count, Png::try_from(&PNG_FILE[..]).unwrap().chunks().len());
+    }
+
+    #[test]
+    fn test_try_from_lenient_repairs_crc() {
+        let mut bytes: Vec<u8> = PNG_FILE.iter().chain(b"trailing").copied().collect();
+        // The last byte of the second chunk's CRC, just before the third.
+        let third_start = Png::try_from(&PNG_FILE[..])
+            .unwrap()
+            .byte_offset_of(2)
+            .unwrap();
+        bytes[third_start - 1] ^= 0xFF;
+
+        assert!(Png::try_from(bytes.as_ref()).is_err());
+
+        let (png, bad_crcs) = Png::try_from_lenient(&bytes).unwrap();
+        assert_eq!(bad_crcs, [1]);
+        assert_eq!(png.trailing_bytes(), b"trailing");
+
+        let repaired = png.as_bytes();
+        assert_eq!(&repaired[..PNG_FILE.len()], PNG_FILE);
+        assert!(Png::try_from(repaired.as_ref()).is_ok());
+    }
+
+    #[test]
+    fn test_try_from_lenient_unreadable() {
+        let (png, bad_crcs) = Png::try_from_lenient(&PNG_FILE[..]).unwrap();
+        assert!(bad_crcs.is_empty());
+        assert_eq!(png.as_bytes(), PNG_FILE);
+
+        let truncated = &PNG_FILE[..PNG_FILE.len() - 20];
+        assert!(matches!(
+            Png::try_from_lenient(truncated),
+            Err(PngError::Unreadable(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_recoverable_truncated() {
+        let truncated = &PNG_FILE[..PNG_FILE.len() - 20];
+        let complete = Png::try_from(&PNG_FILE[..]).unwrap();
+
+        let (png, err) = Png::parse_recoverable(truncated);
+
+        assert!(matches!(
+            err,
+            Some(PngError::BadChunk(ChunkError::InsufficientBytes { .. }))
+        ));
+        assert_eq!(
+            png.chunks(),
+            &complete.chunks()[..complete.chunks().len() - 2]
+        );
+        assert!(png.trailing_bytes().is_empty());
+    }
+
+    #[test]
+    fn test_parse_recoverable_complete() {
+        let (png, err) = Png::parse_recoverable(&PNG_FILE[..]);
+        assert!(err.is_none());
+        assert_eq!(png.as_bytes(), PNG_FILE);
+
+        let (png, err) = Png::parse_recoverable(&PNG_FILE[1..]);
+        assert!(matches!(err, Some(PngError::InvalidSignature { .. })));
+        assert!(png.is_empty());
+    }
+
+    #[test]
+    fn test_try_from_profiled() {
+        let (png, timings) = Png::try_from_profiled(&PNG_FILE[..]).unwrap();
+        assert_eq!(timings.len(), png.chunks().len());
+    }
+
+    #[test]
+    fn test_strip_after_iend() {
+        let hidden = chunk_from_strings("RuSt", "Hidden after the end").unwrap();
+        let payload = b"appended secret payload";
+        let bytes: Vec<u8> = PNG_FILE
+            .iter()
+            .chain(hidden.as_bytes().iter())
+            .chain(payload.iter())
+            .copied()
+            .collect();
+
+        let mut png = Png::try_from(bytes.as_ref()).unwrap();
+        let removed = png.strip_after_iend();
+
+        assert_eq!(removed, (1, payload.len()));
+        assert!(png.trailing_bytes().is_empty());
+        assert!(png.validate().is_ok());
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_unused_ancillary_type() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let mut generated = HashSet::new();
+
+        for idx in 0..20 {
+            let chunk_type = png.unused_ancillary_type(&mut rng);
+            assert!(chunk_type.is_valid());
+            assert!(!chunk_type.is_critical());
+            assert!(generated.insert(chunk_type.bytes()));
+
+            let message = format!("Message number {}", idx);
+            png.append_chunk(Chunk::new(chunk_type, message.into_bytes()));
+        }
+
+        assert_eq!(generated.len(), 20);
+    }
+
+    #[test]
+    fn test_audit_clean() {
+        let report = Png::audit(&PNG_FILE);
+
+        assert!(report.is_clean());
+        assert_eq!(
+            report.chunk_count,
+            Png::try_from(&PNG_FILE[..]).unwrap().len()
+        );
+        assert_eq!(report.unknown_types, ["RuSt"]);
+    }
+
+    #[test]
+    fn test_audit_problems() {
+        let mut bytes = testing_png().as_bytes();
+        // Break the signature and the second chunk's CRC.
+        bytes[1] = b'Q';
+        let first_len = testing_chunks()[0].as_bytes().len();
+        let second_len = testing_chunks()[1].as_bytes().len();
+        bytes[Png::STANDARD_HEADER.len() + first_len + second_len - 1] ^= 0xFF;
+        bytes.extend(b"junk");
+
+        let report = Png::audit(&bytes);
+
+        assert_eq!(
+            report,
+            AuditReport {
+                signature_ok: false,
+                chunk_count: 3,
+                bad_crc_chunks: vec![1],
+                ordering_issues: vec![
+                    "First chunk should be IHDR but was FrSt.".to_string(),
+                    "Last chunk should be IEND but was LASt.".to_string(),
+                ],
+                unknown_types: vec!["FrSt".to_string(), "LASt".to_string(), "miDl".to_string()],
+                trailing_bytes_len: 4,
+            }
+        );
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn test_audit_agrees_with_validate_on_trailing_bytes() {
+        let trailing: Vec<u8> = PNG_FILE.iter().chain(b"trailing").copied().collect();
+        let report = Png::audit(&trailing);
+        assert_eq!(report.trailing_bytes_len, 8);
+        assert!(report.is_clean());
+        assert!(Png::validate_bytes(&trailing).is_ok());
+
+        // Cut off partway into the chunk after IHDR.
+        let ihdr_end = Png::STANDARD_HEADER.len() + CHUNK_METADATA_NUM_BYTES + 13;
+        let truncated = &PNG_FILE[..ihdr_end + 5];
+        assert!(!Png::audit(truncated).is_clean());
+        assert!(Png::validate_bytes(truncated).is_err());
+    }
+
+    #[test]
+    fn test_iter_text() {
+        use crate::codec::deflate_with_level;
+
+        let text = TextEntry::new("Author", "Some One").as_bytes();
+        let ztxt: Vec<u8> = b"Comment\0\0"
+            .iter()
+            .copied()
+            .chain(deflate_with_level(b"Squashed", 6))
+            .collect();
+        let itxt = b"Title\0\0\0en\0\0\xcf\x80".to_vec();
+
+        let mut png = testing_png();
+        for (chunk_type, data) in [("tEXt", text), ("zTXt", ztxt), ("iTXt", itxt)] {
+            png.append_chunk(Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data));
+        }
+
+        let pairs: Vec<(String, String)> = png.iter_text().collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("Author".to_string(), "Some One".to_string()),
+                ("Comment".to_string(), "Squashed".to_string()),
+                ("Title".to_string(), "π".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_chunk() {
+        let png = Png::new()
+            .with_chunk(chunk_from_strings("IHDR", "").unwrap())
+            .with_chunk(chunk_from_strings("IDAT", "").unwrap())
+            .with_chunk(chunk_from_strings("IEND", "").unwrap())
+            .with_chunk(chunk_from_strings("tEXt", "Comment\0Late").unwrap());
+
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["IHDR", "IDAT", "tEXt", "IEND"]);
+        assert!(png.validate().is_ok());
+
+        let reparsed = Png::try_from(png.as_bytes().as_ref()).unwrap();
+        assert_eq!(reparsed.as_bytes(), png.as_bytes());
+    }
+
+    #[test]
+    fn test_validate_bytes_clean() {
+        assert!(Png::validate_bytes(&PNG_FILE).is_ok());
+
+        let trailing: Vec<u8> = PNG_FILE.iter().chain(b"trailing").copied().collect();
+        assert!(Png::validate_bytes(&trailing).is_ok());
+    }
+
+    #[test]
+    fn test_validate_bytes_reports_everything() {
+        let mut bytes = testing_png().as_bytes();
+        // Break the signature and the CRCs of the first two chunks.
+        bytes[1] = b'Q';
+        let first_len = testing_chunks()[0].as_bytes().len();
+        let second_len = testing_chunks()[1].as_bytes().len();
+        bytes[Png::STANDARD_HEADER.len() + first_len - 1] ^= 0xFF;
+        bytes[Png::STANDARD_HEADER.len() + first_len + second_len - 1] ^= 0xFF;
+        bytes.extend(b"junk");
+
+        let errors: Vec<String> = Png::validate_bytes(&bytes)
+            .unwrap_err()
+            .iter()
+            .map(|err| err.to_string())
+            .collect();
+
+        assert_eq!(errors.len(), 6);
+        assert!(errors[0].starts_with("Bytes are missing PNG standard header"));
+        assert_eq!(
+            errors[1..],
+            [
+                "Chunk 0 failed its checksum.",
+                "Chunk 1 failed its checksum.",
+                &format!("Couldn't read a chunk at offset {}.", bytes.len() - 4),
+                "First chunk should be IHDR but was FrSt.",
+                "Last chunk should be IEND but was LASt.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_checksums() {
+        assert!(Png::validate_checksums(&PNG_FILE).is_ok());
+
+        // Out of order chunks are left to `validate_bytes`.
+        assert!(Png::validate_checksums(&testing_png().as_bytes()).is_ok());
+        assert!(Png::validate_bytes(&testing_png().as_bytes()).is_err());
+
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[Png::STANDARD_HEADER.len() + CHUNK_METADATA_NUM_BYTES + 12] ^= 0xFF;
+        let errors = Png::validate_checksums(&bytes).unwrap_err();
+        assert!(matches!(errors[..], [PngError::BadCrc(0)]));
+    }
+
+    #[test]
+    fn test_validate_checksums_not_png() {
+        let errors =
+            Png::validate_checksums(b"Just some text, not an image at all.\n").unwrap_err();
+        assert!(matches!(
+            errors[..],
+            [PngError::InvalidSignature { .. }, PngError::Unreadable(8)]
+        ));
+    }
+
+    #[test]
+    fn test_validate_checksums_truncated() {
+        // Cut off partway into the chunk after IHDR.
+        let ihdr_end = Png::STANDARD_HEADER.len() + CHUNK_METADATA_NUM_BYTES + 13;
+        let errors = Png::validate_checksums(&PNG_FILE[..ihdr_end + 5]).unwrap_err();
+        assert!(matches!(
+            errors[..],
+            [PngError::Unreadable(offset)] if offset == ihdr_end
+        ));
+    }
+
+    #[test]
+    fn test_len() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.len(), png.chunks().len());
+        assert!(!png.is_empty());
+
+        assert!(Png::from_chunks(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_insert_chunk_after_type() {
+        let chunks = ["IHDR", "IDAT", "IDAT", "tEXt", "IEND"]
+            .iter()
+            .map(|chunk_type| chunk_from_strings(chunk_type, "").unwrap())
+            .collect();
+        let mut png = Png::from_chunks(chunks);
+
+        let hidden = chunk_from_strings("ruSt", "Hidden with the image data").unwrap();
+        png.insert_chunk_after_type("IDAT", hidden).unwrap();
+
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["IHDR", "IDAT", "IDAT", "ruSt", "tEXt", "IEND"]);
+    }
+
+    #[test]
+    fn test_split_by_type() {
+        let chunks = ["IHDR", "tEXt", "IDAT", "tEXt", "tEXt", "IEND"]
+            .iter()
+            .map(|chunk_type| chunk_from_strings(chunk_type, "").unwrap())
+            .collect();
+        let mut png = Png::from_chunks(chunks);
+        png.trailing = b"trailing".to_vec();
+
+        let (text, rest) = png.split_by_type("tEXt");
+
+        assert_eq!(text.len(), 3);
+        assert!(text.chunks().iter().all(|chunk| chunk.type_eq_str("tEXt")));
+        assert!(text.trailing_bytes().is_empty());
+
+        let types: Vec<String> = rest
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["IHDR", "IDAT", "IEND"]);
+        assert_eq!(rest.trailing_bytes(), b"trailing");
+    }
+
+    #[test]
+    fn test_insert_chunk() {
+        let mut png = testing_png();
+
+        png.insert_chunk(1, chunk_from_strings("ruSt", "Second").unwrap());
+        png.insert_chunk(4, chunk_from_strings("enDs", "Last").unwrap());
+
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["FrSt", "ruSt", "miDl", "LASt", "enDs"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_chunk_out_of_bounds() {
+        let mut png = testing_png();
+        png.insert_chunk(4, chunk_from_strings("ruSt", "Too far").unwrap());
+    }
+
+    #[test]
+    fn test_insert_chunks_at() {
+        let mut png = testing_png();
+        let inserted = vec![
+            chunk_from_strings("onEa", "One").unwrap(),
+            chunk_from_strings("twOa", "Two").unwrap(),
+            chunk_from_strings("thRa", "Three").unwrap(),
+        ];
+
+        png.insert_chunks_at(1, inserted).unwrap();
+
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["FrSt", "onEa", "twOa", "thRa", "miDl", "LASt"]);
+        assert_eq!(png.chunks()[3].data_as_string().unwrap(), "Three");
+    }
+
+    #[test]
+    fn test_insert_chunks_at_bounds() {
+        let mut png = testing_png();
+        let chunk = || vec![chunk_from_strings("onEa", "One").unwrap()];
+
+        assert!(png.insert_chunks_at(3, chunk()).is_ok());
+        assert_eq!(png.last_chunk().unwrap().chunk_type().to_string(), "onEa");
+
+        let result = png.insert_chunks_at(10, chunk());
+        assert!(matches!(
+            result,
+            Err(PngError::IndexOutOfBounds { index: 10, len: 4 })
+        ));
+        assert_eq!(png.chunks().len(), 4);
+    }
+
+    #[test]
+    fn test_insert_chunk_after_missing_type() {
+        let mut png = testing_png();
+        let chunk = chunk_from_strings("ruSt", "Nowhere to go").unwrap();
+
+        let result = png.insert_chunk_after_type("IDAT", chunk);
+
+        assert!(matches!(result, Err(PngError::MissingChunkType(_))));
+        assert_eq!(png.chunks().len(), 3);
+    }
+
+    #[test]
+    fn test_chunks_as_bytes_round_trip() {
+        let png = testing_png();
+        let stream = png.chunks_as_bytes();
+        assert_eq!(stream, png.as_bytes()[Png::STANDARD_HEADER.len()..]);
+
+        let bytes: Vec<u8> = Png::STANDARD_HEADER
+            .iter()
+            .chain(stream.iter())
+            .copied()
+            .collect();
+        let imported = Png::try_from(bytes.as_ref()).unwrap();
+
+        assert_eq!(imported.chunks_as_bytes(), stream);
+        assert_eq!(imported.as_bytes(), png.as_bytes());
+    }
+
+    #[test]
+    fn test_digest() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let same = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.digest(), same.digest());
+
+        let mut modified = Png::try_from(&PNG_FILE[..]).unwrap();
+        modified.append_chunk(chunk_from_strings("ruSt", "Changed").unwrap());
+        assert_ne!(png.digest(), modified.digest());
+    }
+
+    #[test]
+    fn test_chunk_data_concat() {
+        let chunks = [
+            ("IHDR", "head"),
+            ("IDAT", "one"),
+            ("IDAT", "two"),
+            ("IEND", ""),
+        ]
+        .iter()
+        .map(|(chunk_type, data)| chunk_from_strings(chunk_type, data).unwrap())
+        .collect();
+        let png = Png::from_chunks(chunks);
+
+        assert_eq!(png.chunk_data_concat("IDAT"), b"onetwo");
+        assert!(png.chunk_data_concat("tEXt").is_empty());
+    }
+
+    #[test]
+    fn test_digest_of_type_ignores_metadata() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let mut tagged = Png::try_from(&PNG_FILE[..]).unwrap();
+        tagged.append_chunk(chunk_from_strings("tEXt", "Comment\0Tagged").unwrap());
+
+        assert_ne!(png.digest(), tagged.digest());
+        assert_eq!(png.digest_of_type("IDAT"), tagged.digest_of_type("IDAT"));
+        assert_ne!(png.digest_of_type("IDAT"), png.digest_of_type("RuSt"));
+    }
+
     #[test]
     fn test_png_trait_impls() {
         let chunk_bytes: Vec<u8> = testing_chunks()
@@ -0,0 +1,290 @@
+use std::io;
+
+use thiserror::Error;
+
+use crate::{
+    codec::inflate,
+    ihdr::{Ihdr, Interlace},
+};
+
+// Anything bigger would take a while to decode, only to be averaged away.
+pub const MAX_PREVIEW_PIXELS: u64 = 1 << 20;
+
+// Terminal characters are roughly twice as tall as they are wide.
+const CELL_ASPECT: usize = 2;
+
+// From dark to bright, as seen on a dark terminal.
+const RAMP: &[u8] = b" .:-=+*#%@";
+
+#[derive(Error, Debug)]
+pub enum PreviewError {
+    #[error("Image is {0}x{1}, too large to preview.")]
+    TooLarge(u32, u32),
+    #[error("Interlaced images can't be previewed.")]
+    Interlaced,
+    #[error("Color type {0} at bit depth {1} can't be previewed.")]
+    Unsupported(u8, u8),
+    #[error("Image data couldn't be decompressed: {0}")]
+    Inflate(#[from] io::Error),
+    #[error("Row {0} has unknown filter type {1}.")]
+    UnknownFilter(usize, u8),
+    #[error("Image data should be {expected} bytes but was {found}.")]
+    WrongLength { expected: usize, found: usize },
+}
+
+// The image reduced to one brightness value per pixel, row by row.
+pub struct Luma {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Luma {
+    fn get(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+}
+
+// Only 8-bit grayscale and truecolor, with or without alpha, are handled.
+fn channels(ihdr: &Ihdr) -> Result<usize, PreviewError> {
+    match (ihdr.color_type(), ihdr.bit_depth()) {
+        (0, 8) => Ok(1),
+        (2, 8) => Ok(3),
+        (4, 8) => Ok(2),
+        (6, 8) => Ok(4),
+        (color_type, bit_depth) => Err(PreviewError::Unsupported(color_type, bit_depth)),
+    }
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = left as i16 + up as i16 - up_left as i16;
+    let to_left = (estimate - left as i16).abs();
+    let to_up = (estimate - up as i16).abs();
+    let to_up_left = (estimate - up_left as i16).abs();
+
+    if to_left <= to_up && to_left <= to_up_left {
+        left
+    } else if to_up <= to_up_left {
+        up
+    } else {
+        up_left
+    }
+}
+
+// Reverses the filter in front of each scanline, giving the raw samples.
+fn unfilter(
+    data: &[u8],
+    row_bytes: usize,
+    pixel_bytes: usize,
+    height: usize,
+) -> Result<Vec<u8>, PreviewError> {
+    let expected = (row_bytes + 1) * height;
+    if data.len() != expected {
+        return Err(PreviewError::WrongLength {
+            expected,
+            found: data.len(),
+        });
+    }
+
+    let mut samples = vec![0; row_bytes * height];
+    for (y, line) in data.chunks(row_bytes + 1).enumerate() {
+        let (filter, line) = (line[0], &line[1..]);
+        let (done, rest) = samples.split_at_mut(y * row_bytes);
+        let previous = (y > 0).then(|| &done[(y - 1) * row_bytes..]);
+        let row = &mut rest[..row_bytes];
+
+        for x in 0..row_bytes {
+            let left = if x >= pixel_bytes {
+                row[x - pixel_bytes]
+            } else {
+                0
+            };
+            let up = previous.map_or(0, |prev| prev[x]);
+            let up_left = match previous {
+                Some(prev) if x >= pixel_bytes => prev[x - pixel_bytes],
+                _ => 0,
+            };
+
+            let predicted = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(PreviewError::UnknownFilter(y, filter)),
+            };
+            row[x] = line[x].wrapping_add(predicted);
+        }
+    }
+
+    Ok(samples)
+}
+
+// Decodes the image data far enough to know how bright each pixel is.
+pub fn decode_luma(ihdr: &Ihdr, idat: &[u8]) -> Result<Luma, PreviewError> {
+    let (width, height) = (ihdr.width(), ihdr.height());
+    if width as u64 * height as u64 > MAX_PREVIEW_PIXELS {
+        return Err(PreviewError::TooLarge(width, height));
+    }
+    if ihdr.interlace() != Interlace::None {
+        return Err(PreviewError::Interlaced);
+    }
+
+    let channels = channels(ihdr)?;
+    let (width, height) = (width as usize, height as usize);
+    let samples = unfilter(&inflate(idat)?, width * channels, channels, height)?;
+
+    let pixels = samples
+        .chunks(channels)
+        .map(|pixel| {
+            let (color, alpha) = match channels {
+                2 | 4 => (&pixel[..channels - 1], pixel[channels - 1] as u32),
+                _ => (pixel, 255),
+            };
+            let luma = match color {
+                [r, g, b] => (299 * *r as u32 + 587 * *g as u32 + 114 * *b as u32) / 1000,
+                [gray] => *gray as u32,
+                _ => unreachable!("Pixels have one or three color samples"),
+            };
+            // Transparent pixels fade into the background.
+            (luma * alpha / 255) as u8
+        })
+        .collect();
+
+    Ok(Luma {
+        width,
+        height,
+        pixels,
+    })
+}
+
+// The preview's size in characters, keeping the image's proportions.
+pub fn preview_size(width: usize, height: usize, max_columns: usize) -> (usize, usize) {
+    let columns = width.min(max_columns).max(1);
+    let rows = (height * columns)
+        .div_ceil(width.max(1) * CELL_ASPECT)
+        .max(1);
+    (columns, rows)
+}
+
+// Each character stands for the average brightness of the pixels under it.
+pub fn render_ascii(luma: &Luma, max_columns: usize) -> Vec<String> {
+    let (columns, rows) = preview_size(luma.width, luma.height, max_columns);
+    if luma.width == 0 || luma.height == 0 {
+        return Vec::new();
+    }
+
+    (0..rows)
+        .map(|row| {
+            let (top, bottom) = (row * luma.height / rows, (row + 1) * luma.height / rows);
+            (0..columns)
+                .map(|column| {
+                    let left = column * luma.width / columns;
+                    let right = (column + 1) * luma.width / columns;
+                    let (x_range, y_range) = (left..right.max(left + 1), top..bottom.max(top + 1));
+
+                    let cells = x_range.len() * y_range.len();
+                    let total: usize = y_range
+                        .flat_map(|y| x_range.clone().map(move |x| luma.get(x, y) as usize))
+                        .sum();
+
+                    RAMP[total / cells * RAMP.len() / 256] as char
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::deflate;
+
+    fn ihdr(width: u32, height: u32, color_type: u8, interlace: u8) -> Ihdr {
+        let bytes = [
+            width.to_be_bytes().as_slice(),
+            &height.to_be_bytes(),
+            &[8, color_type, 0, 0, interlace],
+        ]
+        .concat();
+        Ihdr::try_from(bytes.as_slice()).unwrap()
+    }
+
+    // A 4x2 grayscale image, black on the left and white on the right, with
+    // each row using a different filter.
+    fn testing_scanlines() -> Vec<u8> {
+        #[rustfmt::skip]
+        let scanlines = vec![
+            1, 0, 0, 255, 0,    // Sub
+            2, 0, 0, 0, 0,      // Up
+        ];
+        scanlines
+    }
+
+    #[test]
+    fn test_decode_luma_unfilters() {
+        let luma = decode_luma(&ihdr(4, 2, 0, 0), &deflate(&testing_scanlines())).unwrap();
+        assert_eq!(luma.pixels, [0, 0, 255, 255, 0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_decode_luma_paeth_and_average() {
+        #[rustfmt::skip]
+        let scanlines = [
+            0, 10, 20, 30,
+            3, 5, 5, 5,         // Average
+            4, 1, 1, 1,         // Paeth
+        ];
+        let luma = decode_luma(&ihdr(3, 3, 0, 0), &deflate(&scanlines)).unwrap();
+        assert_eq!(luma.pixels, [10, 20, 30, 10, 20, 30, 11, 21, 31]);
+    }
+
+    #[test]
+    fn test_decode_luma_truecolor_alpha() {
+        let scanlines = [0, 255, 255, 255, 255, 255, 255, 255, 0];
+        let luma = decode_luma(&ihdr(2, 1, 6, 0), &deflate(&scanlines)).unwrap();
+        assert_eq!(luma.pixels, [255, 0]);
+    }
+
+    #[test]
+    fn test_decode_luma_unsupported() {
+        let idat = deflate(&testing_scanlines());
+        assert!(matches!(
+            decode_luma(&ihdr(4, 2, 3, 0), &idat),
+            Err(PreviewError::Unsupported(3, 8))
+        ));
+        assert!(matches!(
+            decode_luma(&ihdr(4, 2, 0, 1), &idat),
+            Err(PreviewError::Interlaced)
+        ));
+        assert!(matches!(
+            decode_luma(&ihdr(4096, 4096, 0, 0), &idat),
+            Err(PreviewError::TooLarge(4096, 4096))
+        ));
+        assert!(matches!(
+            decode_luma(&ihdr(4, 3, 0, 0), &idat),
+            Err(PreviewError::WrongLength {
+                expected: 15,
+                found: 10
+            })
+        ));
+    }
+
+    #[test]
+    fn test_render_ascii_dimensions() {
+        let luma = decode_luma(&ihdr(4, 2, 0, 0), &deflate(&testing_scanlines())).unwrap();
+
+        let lines = render_ascii(&luma, 80);
+        assert_eq!(lines, ["  @@"]);
+
+        let lines = render_ascii(&luma, 2);
+        assert_eq!(lines, [" @"]);
+    }
+
+    #[test]
+    fn test_preview_size() {
+        assert_eq!(preview_size(50, 50, 64), (50, 25));
+        assert_eq!(preview_size(200, 100, 64), (64, 16));
+        assert_eq!(preview_size(10, 1, 64), (10, 1));
+    }
+}
@@ -0,0 +1,99 @@
+use std::io::Read;
+use std::path::Path;
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum RemoteError {
+    #[cfg(not(feature = "url"))]
+    #[error("pngme was built without URL support; rebuild with `--features url`.")]
+    Unsupported,
+    #[cfg(feature = "url")]
+    #[error(transparent)]
+    Request(#[from] Box<ureq::Error>),
+}
+
+// Paths are treated as URLs only with an explicit http(s) scheme, so a local
+// file can never be mistaken for one.
+pub fn is_url(path: &Path) -> bool {
+    path.to_str()
+        .is_some_and(|path| path.starts_with("http://") || path.starts_with("https://"))
+}
+
+// The body is read as it arrives, just as a file would be.
+#[cfg(feature = "url")]
+pub fn open_url(url: &str) -> Result<Box<dyn Read>, RemoteError> {
+    let response = ureq::get(url).call().map_err(Box::new)?;
+    Ok(Box::new(response.into_reader()))
+}
+
+#[cfg(not(feature = "url"))]
+pub fn open_url(_url: &str) -> Result<Box<dyn Read>, RemoteError> {
+    Err(RemoteError::Unsupported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_url() {
+        assert!(is_url(Path::new("https://example.com/image.png")));
+        assert!(is_url(Path::new("http://localhost:8080/image.png")));
+        assert!(!is_url(Path::new("image.png")));
+        assert!(!is_url(Path::new("./http://image.png")));
+        assert!(!is_url(Path::new("ftp://example.com/image.png")));
+    }
+
+    #[cfg(not(feature = "url"))]
+    #[test]
+    fn test_open_url_unsupported() {
+        assert!(matches!(
+            open_url("https://example.com/image.png"),
+            Err(RemoteError::Unsupported)
+        ));
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn test_open_url_fetches_png() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+        use std::thread;
+
+        use crate::{chunk::Chunk, chunk_type::ChunkType, png::Png};
+
+        let png = Png::from_chunks(vec![
+            Chunk::new(ChunkType::IHDR, vec![0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0]),
+            Chunk::new(ChunkType::IEND, Vec::new()),
+        ]);
+        let body = png.as_bytes();
+
+        // Serves the one request with the PNG, then closes the connection.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+        });
+
+        let reader = open_url(&format!("http://{}/image.png", addr)).unwrap();
+        let fetched = Png::from_reader(reader).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(fetched.as_bytes(), png.as_bytes());
+    }
+}
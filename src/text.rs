@@ -0,0 +1,294 @@
+use std::io;
+use std::string::FromUtf8Error;
+use thiserror::Error;
+
+use crate::codec::inflate;
+
+pub const MAX_KEYWORD_NUM_BYTES: usize = 79;
+
+#[derive(Error, Debug)]
+pub enum TextError {
+    #[error("Text data is missing the null separator after its keyword.")]
+    MissingSeparator,
+    #[error("Text data ends before its compression method.")]
+    Truncated,
+    #[error("Unknown text compression method {0}.")]
+    UnknownCompression(u8),
+    #[error("Compressed text couldn't be decompressed: {0}")]
+    Decompress(#[from] io::Error),
+    #[error(transparent)]
+    NotUtf8(#[from] FromUtf8Error),
+    #[error(
+        "Keywords must be 1 to {} characters long, but {0:?} is {1}.",
+        MAX_KEYWORD_NUM_BYTES
+    )]
+    KeywordLength(String, usize),
+    #[error("Keyword {0:?} contains {1:?}, which isn't printable Latin-1.")]
+    KeywordNotLatin1(String, char),
+    #[error("Text contains {0:?}, which can't be represented in Latin-1.")]
+    ValueNotLatin1(char),
+}
+
+// The keyword/value pair held by a text chunk. For tEXt both are Latin-1
+// encoded and separated by a single null byte.
+#[derive(PartialEq, Eq, Debug)]
+pub struct TextEntry {
+    keyword: String,
+    value: String,
+}
+
+impl TextEntry {
+    pub fn new(keyword: &str, value: &str) -> TextEntry {
+        Self {
+            keyword: keyword.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    // Keywords are restricted by the spec, unlike values, which only need to be
+    // representable in Latin-1. Neither is ever rewritten to make it fit.
+    pub fn new_checked(keyword: &str, value: &str) -> Result<TextEntry, TextError> {
+        if let Some(c) = keyword.chars().find(|c| !is_keyword_char(*c)) {
+            return Err(TextError::KeywordNotLatin1(keyword.to_string(), c));
+        }
+        let length = keyword.chars().count();
+        if !(1..=MAX_KEYWORD_NUM_BYTES).contains(&length) {
+            return Err(TextError::KeywordLength(keyword.to_string(), length));
+        }
+        if let Some(c) = value.chars().find(|c| u8::try_from(*c).is_err()) {
+            return Err(TextError::ValueNotLatin1(c));
+        }
+
+        Ok(Self::new(keyword, value))
+    }
+
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        string_to_latin1(&self.keyword)
+            .into_iter()
+            .chain([0])
+            .chain(string_to_latin1(&self.value))
+            .collect()
+    }
+
+    // zTXt holds a keyword, a compression method byte, then Latin-1 text
+    // compressed with that method.
+    pub fn from_ztxt(bytes: &[u8]) -> Result<TextEntry, TextError> {
+        let (keyword, remaining_bytes) = split_keyword(bytes)?;
+        let (method, compressed) = remaining_bytes.split_first().ok_or(TextError::Truncated)?;
+
+        Ok(Self {
+            keyword,
+            value: latin1_to_string(&decompress(*method, compressed)?),
+        })
+    }
+
+    // iTXt holds a keyword, a compression flag and method, a language tag
+    // and translated keyword (both ignored here), then UTF-8 text which is
+    // compressed if the flag is set.
+    pub fn from_itxt(bytes: &[u8]) -> Result<TextEntry, TextError> {
+        let (keyword, remaining_bytes) = split_keyword(bytes)?;
+        let [flag, method, remaining_bytes @ ..] = remaining_bytes else {
+            return Err(TextError::Truncated);
+        };
+        let (_language, remaining_bytes) = split_keyword(remaining_bytes)?;
+        let (_translated_keyword, text) = split_keyword(remaining_bytes)?;
+
+        let text = if *flag == 0 {
+            text.to_vec()
+        } else {
+            decompress(*method, text)?
+        };
+
+        Ok(Self {
+            keyword,
+            value: String::from_utf8(text)?,
+        })
+    }
+}
+
+// Splits the keyword off the front of text chunk data, returning it with the
+// bytes following its null separator.
+fn split_keyword(bytes: &[u8]) -> Result<(String, &[u8]), TextError> {
+    let separator = bytes
+        .iter()
+        .position(|byte| *byte == 0)
+        .ok_or(TextError::MissingSeparator)?;
+
+    Ok((
+        latin1_to_string(&bytes[..separator]),
+        &bytes[separator + 1..],
+    ))
+}
+
+// Zlib (method 0) is the only compression method defined by the spec.
+fn decompress(method: u8, bytes: &[u8]) -> Result<Vec<u8>, TextError> {
+    match method {
+        0 => Ok(inflate(bytes)?),
+        _ => Err(TextError::UnknownCompression(method)),
+    }
+}
+
+// Printable Latin-1 only, i.e. no control characters or non-breaking space.
+fn is_keyword_char(c: char) -> bool {
+    matches!(c, ' '..='~' | '\u{a1}'..='\u{ff}')
+}
+
+pub fn latin1_to_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| char::from(*byte)).collect()
+}
+
+// Characters outside of Latin-1 can't be represented, and are replaced by '?'.
+fn string_to_latin1(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| u8::try_from(c).unwrap_or(b'?'))
+        .collect()
+}
+
+impl TryFrom<&[u8]> for TextEntry {
+    type Error = TextError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let (keyword, remaining_bytes) = split_keyword(value)?;
+
+        Ok(Self {
+            keyword,
+            value: latin1_to_string(remaining_bytes),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::deflate_with_level;
+
+    #[test]
+    fn test_text_entry_from_bytes() {
+        let entry = TextEntry::try_from(b"Author\0Some One".as_ref()).unwrap();
+        assert_eq!(entry.keyword(), "Author");
+        assert_eq!(entry.value(), "Some One");
+    }
+
+    #[test]
+    fn test_text_entry_latin1() {
+        let entry = TextEntry::try_from(b"Title\0Caf\xe9".as_ref()).unwrap();
+        assert_eq!(entry.value(), "Café");
+    }
+
+    #[test]
+    fn test_text_entry_as_bytes() {
+        let entry = TextEntry::new("Title", "Café");
+        assert_eq!(entry.as_bytes(), b"Title\0Caf\xe9");
+        assert_eq!(
+            TextEntry::try_from(entry.as_bytes().as_ref()).unwrap(),
+            entry
+        );
+    }
+
+    #[test]
+    fn test_text_entry_as_bytes_non_latin1() {
+        let entry = TextEntry::new("Title", "π");
+        assert_eq!(entry.as_bytes(), b"Title\0?");
+    }
+
+    #[test]
+    fn test_new_checked_value_not_latin1() {
+        assert!(matches!(
+            TextEntry::new_checked("Title", "π"),
+            Err(TextError::ValueNotLatin1('π'))
+        ));
+
+        let entry = TextEntry::new_checked("Title", "Café").unwrap();
+        assert_eq!(entry.as_bytes(), b"Title\0Caf\xe9");
+    }
+
+    #[test]
+    fn test_new_checked_latin1_bytes_verbatim() {
+        let bytes = b"Caf\xe9 \xa9 2024";
+        let entry = TextEntry::new_checked("Copyright", &latin1_to_string(bytes)).unwrap();
+
+        let stored = entry.as_bytes();
+        assert_eq!(stored[b"Copyright\0".len()..], bytes[..]);
+    }
+
+    #[test]
+    fn test_text_entry_from_ztxt() {
+        let bytes: Vec<u8> = b"Comment\0\0"
+            .iter()
+            .copied()
+            .chain(deflate_with_level(b"Caf\xe9 au lait", 6))
+            .collect();
+
+        let entry = TextEntry::from_ztxt(&bytes).unwrap();
+        assert_eq!(entry, TextEntry::new("Comment", "Café au lait"));
+
+        let entry = TextEntry::from_ztxt(b"Comment\0\x01data");
+        assert!(matches!(entry, Err(TextError::UnknownCompression(1))));
+    }
+
+    #[test]
+    fn test_text_entry_from_itxt() {
+        let entry = TextEntry::from_itxt(b"Title\0\0\0en\0Titel\0\xcf\x80").unwrap();
+        assert_eq!(entry, TextEntry::new("Title", "π"));
+
+        let bytes: Vec<u8> = b"Title\0\x01\0\0\0"
+            .iter()
+            .copied()
+            .chain(deflate_with_level("π".as_bytes(), 6))
+            .collect();
+        let entry = TextEntry::from_itxt(&bytes).unwrap();
+        assert_eq!(entry, TextEntry::new("Title", "π"));
+
+        assert!(matches!(
+            TextEntry::from_itxt(b"Title\0"),
+            Err(TextError::Truncated)
+        ));
+    }
+
+    #[test]
+    fn test_text_entry_new_checked() {
+        let entry = TextEntry::new_checked("Comment", "Hidden message").unwrap();
+        assert_eq!(entry.as_bytes(), b"Comment\0Hidden message");
+
+        let keyword = "K".repeat(MAX_KEYWORD_NUM_BYTES);
+        assert!(TextEntry::new_checked(&keyword, "").is_ok());
+        assert!(TextEntry::new_checked("Légende", "").is_ok());
+    }
+
+    #[test]
+    fn test_text_entry_new_checked_invalid_keyword() {
+        assert!(matches!(
+            TextEntry::new_checked("", "value"),
+            Err(TextError::KeywordLength(_, 0))
+        ));
+
+        let keyword = "K".repeat(MAX_KEYWORD_NUM_BYTES + 1);
+        assert!(matches!(
+            TextEntry::new_checked(&keyword, "value"),
+            Err(TextError::KeywordLength(_, 80))
+        ));
+
+        assert!(matches!(
+            TextEntry::new_checked("Key\0word", "value"),
+            Err(TextError::KeywordNotLatin1(_, '\0'))
+        ));
+        assert!(matches!(
+            TextEntry::new_checked("Pi π", "value"),
+            Err(TextError::KeywordNotLatin1(_, 'π'))
+        ));
+    }
+
+    #[test]
+    fn test_text_entry_missing_separator() {
+        let entry = TextEntry::try_from(b"Author".as_ref());
+        assert!(entry.is_err());
+    }
+}